@@ -1,23 +1,64 @@
-use crate::errors::AmuriError;
+use crate::builder::AssetModelBuilder;
+use crate::errors::{AmuriError, UriComponent};
+use crate::history::PublishHistory;
 use crate::level::{Level, OwnedLevel};
+use crate::parse::{query::parse_query_value, resource::parse_resource, uri::parse_uri};
+use crate::route::{RouteLayout, PATH_COMPONENTS};
 use crate::scheme::Scheme;
+use crate::timestamp::Timestamp;
 use crate::version::Version;
+use nom::combinator::all_consuming;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// A parsed asset management uri.
+///
+/// The components of the path which a model carries follow its route
+/// layout, which is that of its scheme unless set via `with_route_layout`
+/// (see `RouteLayout`). Components outside of the layout, such as the
+/// department of a plate, are never set, so that a model whose layout is
+/// complete writes a uri which `parse_uri` parses back into it.
+///
+/// Models are ordered by scheme, level, name, source, department,
+/// subcontext, layer, pass, snapshot type, version, as_of, query parameters,
+/// key, resource path and finally route layout. See `Level` and `Version`
+/// for how those components order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct AssetModel<'a> {
     container_type: Scheme,
     level: Level<'a>,
     name: &'a str,
-    department: &'a str,
-    subcontext: &'a str,
+    /// The name of the asset from which an instance derives
+    source: Option<&'a str>,
+    department: Option<&'a str>,
+    subcontext: Option<&'a str>,
+    /// The render layer
+    layer: Option<&'a str>,
+    /// The render pass
+    pass: Option<&'a str>,
     snapshot_type: &'a str,
     version: Option<Version>,
+    /// The point in time at which the version should be resolved
+    as_of: Option<Timestamp>,
+    /// Query parameters other than the version and as_of, ordered by key
+    /// rather than as written
+    query: BTreeMap<&'a str, &'a str>,
     /// The particular filetype we are interested in
     key: Option<&'a str>,
+    /// Segments of the path to an individual resource within the snapshot
+    resource_path: Vec<&'a str>,
+    /// The components of the path
+    layout: RouteLayout,
 }
 
 impl<'a> AssetModel<'a> {
+    /// constructor function. The department and subcontext are ignored if
+    /// the scheme's route has none (eg plate); components of the route
+    /// beyond them (eg the layer and pass of a render) are set via
+    /// `with_component`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         container_type: Scheme,
         level: Level<'a>,
@@ -27,20 +68,45 @@ impl<'a> AssetModel<'a> {
         snapshot_type: &'a str,
         version: Option<Version>,
         key: Option<&'a str>,
+    ) -> Self {
+        Self::minimal(container_type, level, name, snapshot_type)
+            .with_department(department)
+            .with_subcontext(subcontext)
+            .with_version(version)
+            .with_key(key)
+    }
+
+    /// Construct a model with only the components common to every route
+    /// layout: the scheme, level, name and snapshot type. The remaining
+    /// components of the scheme's layout are set via `with_component`
+    pub fn minimal(
+        container_type: Scheme,
+        level: Level<'a>,
+        name: &'a str,
+        snapshot_type: &'a str,
     ) -> Self {
         Self {
-            container_type,
             level,
             name,
-            department,
-            subcontext,
+            source: None,
+            department: None,
+            subcontext: None,
+            layer: None,
+            pass: None,
             snapshot_type,
-            version,
-            /// The particular filetype we are interested in
-            key,
+            version: None,
+            as_of: None,
+            query: BTreeMap::new(),
+            key: None,
+            resource_path: Vec::new(),
+            layout: container_type.route_layout(),
+            container_type,
         }
     }
 
+    /// Construct an AssetModel following the default route layout from strs,
+    /// validating each component.
+    #[allow(clippy::too_many_arguments)]
     pub fn from_strs(
         container_type: &'a str,
         level: &'a str,
@@ -51,37 +117,442 @@ impl<'a> AssetModel<'a> {
         version: Option<&'a str>,
         key: Option<&'a str>,
     ) -> std::result::Result<AssetModel<'a>, AmuriError> {
-        let container_type = Scheme::from_str(container_type)?;
-        let level = Level::from_str(level)?;
-        let version = if version.is_some() {
-            Some(Version::from_str(version.unwrap())?)
-        } else {
-            None
-        };
-        Ok(AssetModel::new(
+        let mut builder = AssetModelBuilder::new()
+            .container_type(Scheme::from_str(container_type)?)
+            .level(level)
+            .name(name)
+            .department(department)
+            .subcontext(subcontext)
+            .snapshot_type(snapshot_type);
+        if let Some(version) = version {
+            builder = builder.version(Version::from_str(version)?);
+        }
+        if let Some(key) = key {
+            builder = builder.key(key);
+        }
+        builder.build()
+    }
+
+    /// Retrieve the scheme (container type) of the model
+    pub fn container_type(&self) -> &Scheme {
+        &self.container_type
+    }
+
+    /// Retrieve the level of the model
+    pub fn level(&self) -> &Level<'a> {
+        &self.level
+    }
+
+    /// Retrieve the container name
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Retrieve the name of the asset from which an instance derives, if the
+    /// route has one
+    pub fn source(&self) -> Option<&'a str> {
+        self.source
+    }
+
+    /// Retrieve the department. This is empty if the route has no
+    /// department (eg a plate); see `component`
+    pub fn department(&self) -> &'a str {
+        self.department.unwrap_or("")
+    }
+
+    /// Retrieve the subcontext. This is empty if the route has no
+    /// subcontext (eg a plate); see `component`
+    pub fn subcontext(&self) -> &'a str {
+        self.subcontext.unwrap_or("")
+    }
+
+    /// Retrieve the render layer, if the route has one
+    pub fn layer(&self) -> Option<&'a str> {
+        self.layer
+    }
+
+    /// Retrieve the render pass, if the route has one
+    pub fn pass(&self) -> Option<&'a str> {
+        self.pass
+    }
+
+    /// Retrieve the snapshot type
+    pub fn snapshot_type(&self) -> &'a str {
+        self.snapshot_type
+    }
+
+    /// Retrieve the version, if one was specified
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    /// Retrieve the point in time at which the version should be resolved,
+    /// if one was specified
+    pub fn as_of(&self) -> Option<Timestamp> {
+        self.as_of
+    }
+
+    /// Resolve the version against the publish history, as of the model's
+    /// as_of timestamp or, if it has none, against everything the history
+    /// records. A model without a version resolves the current version
+    pub fn resolve_version(&self, history: &dyn PublishHistory) -> Result<u32, AmuriError> {
+        // no event can be recorded later than the last representable second
+        let as_of = self.as_of.unwrap_or(Timestamp::from_secs(u64::MAX));
+        self.version
+            .as_ref()
+            .unwrap_or(&Version::Current)
+            .resolve_as_of(history, as_of)
+    }
+
+    /// Retrieve the value of a query parameter other than the version and
+    /// as_of. If a uri repeats a parameter, the first value is kept
+    pub fn query_param(&self, key: &str) -> Option<&'a str> {
+        self.query.get(key).copied()
+    }
+
+    /// Iterate over the query parameters other than the version and as_of,
+    /// ordered by key. The order in which they were written is not preserved
+    pub fn query_params(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.query.iter().map(|(k, v)| (*k, *v))
+    }
+
+    /// Retrieve the key, if one was specified
+    pub fn key(&self) -> Option<&'a str> {
+        self.key
+    }
+
+    /// Retrieve the value of a component of the path (see `PATH_COMPONENTS`)
+    /// or the key, if the model has one
+    pub fn component(&self, component: UriComponent) -> Option<&'a str> {
+        match component {
+            UriComponent::Name => Some(self.name),
+            UriComponent::Source => self.source,
+            UriComponent::Department => self.department,
+            UriComponent::Subcontext => self.subcontext,
+            UriComponent::Layer => self.layer,
+            UriComponent::Pass => self.pass,
+            UriComponent::SnapshotType => Some(self.snapshot_type),
+            UriComponent::Key => self.key,
+            _ => None,
+        }
+    }
+
+    /// Retrieve the segments of the resource path. This is empty if no
+    /// resource path was specified
+    pub fn resource_path(&self) -> &[&'a str] {
+        &self.resource_path
+    }
+
+    /// Retrieve the layout of the path of the model's route
+    pub fn route_layout(&self) -> &RouteLayout {
+        &self.layout
+    }
+
+    /// Return a copy of the model with the supplied scheme, adopting the
+    /// scheme's route layout (see `with_route_layout`)
+    pub fn with_container_type(&self, container_type: Scheme) -> Self {
+        let layout = container_type.route_layout();
+        Self {
             container_type,
+            ..self.clone()
+        }
+        .with_route_layout(layout)
+    }
+
+    /// Return a copy of the model following the supplied route layout (eg
+    /// one registered for its scheme with a `SchemeRegistry`). Components
+    /// outside of the layout are cleared
+    pub fn with_route_layout(&self, layout: RouteLayout) -> Self {
+        let mut model = self.clone();
+        for component in PATH_COMPONENTS.iter().copied() {
+            if !layout.contains(component) {
+                model.set_component(component, None);
+            }
+        }
+        model.layout = layout;
+        model
+    }
+
+    // set or clear a component of the path, without consulting the layout.
+    // The name and snapshot type can not be cleared
+    fn set_component(&mut self, component: UriComponent, value: Option<&'a str>) {
+        match component {
+            UriComponent::Name => self.name = value.unwrap_or(self.name),
+            UriComponent::Source => self.source = value,
+            UriComponent::Department => self.department = value,
+            UriComponent::Subcontext => self.subcontext = value,
+            UriComponent::Layer => self.layer = value,
+            UriComponent::Pass => self.pass = value,
+            UriComponent::SnapshotType => self.snapshot_type = value.unwrap_or(self.snapshot_type),
+            UriComponent::Key => self.key = value,
+            _ => {}
+        }
+    }
+
+    /// Return a copy of the model with the supplied level
+    pub fn with_level(&self, level: Level<'a>) -> Self {
+        Self {
             level,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied container name
+    pub fn with_name(&self, name: &'a str) -> Self {
+        Self {
             name,
-            department,
-            subcontext,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied value for a component of
+    /// the path (see `PATH_COMPONENTS`) or the key. Returns an error if the
+    /// model's route has no such component (eg the department of a plate)
+    pub fn with_component(
+        &self,
+        component: UriComponent,
+        value: &'a str,
+    ) -> Result<Self, AmuriError> {
+        check_route_has(&self.container_type, &self.layout, component, value)?;
+        let mut model = self.clone();
+        model.set_component(component, Some(value));
+        Ok(model)
+    }
+
+    /// Return a copy of the model without an optional component of its
+    /// route (eg the source of an instance) or the key. Returns an error if
+    /// the route requires the component
+    pub fn without_component(&self, component: UriComponent) -> Result<Self, AmuriError> {
+        check_optional(&self.layout, component)?;
+        let mut model = self.clone();
+        model.set_component(component, None);
+        Ok(model)
+    }
+
+    /// Return a copy of the model with the supplied source asset name. This
+    /// has no effect if the route has no source; see `with_component`
+    pub fn with_source(&self, source: &'a str) -> Self {
+        self.with_route_component(UriComponent::Source, source)
+    }
+
+    /// Return a copy of the model with the supplied department. This has no
+    /// effect if the route has no department; see `with_component`
+    pub fn with_department(&self, department: &'a str) -> Self {
+        self.with_route_component(UriComponent::Department, department)
+    }
+
+    /// Return a copy of the model with the supplied subcontext. This has no
+    /// effect if the route has no subcontext; see `with_component`
+    pub fn with_subcontext(&self, subcontext: &'a str) -> Self {
+        self.with_route_component(UriComponent::Subcontext, subcontext)
+    }
+
+    /// Return a copy of the model with the supplied render layer. This has
+    /// no effect if the route has no layer; see `with_component`
+    pub fn with_layer(&self, layer: &'a str) -> Self {
+        self.with_route_component(UriComponent::Layer, layer)
+    }
+
+    /// Return a copy of the model with the supplied render pass. This has no
+    /// effect if the route has no pass; see `with_component`
+    pub fn with_pass(&self, pass: &'a str) -> Self {
+        self.with_route_component(UriComponent::Pass, pass)
+    }
+
+    // set a component of the path, leaving the model unchanged if its route
+    // has no such component
+    fn with_route_component(&self, component: UriComponent, value: &'a str) -> Self {
+        self.with_component(component, value)
+            .unwrap_or_else(|_| self.clone())
+    }
+
+    /// Return a copy of the model with the supplied snapshot type
+    pub fn with_snapshot_type(&self, snapshot_type: &'a str) -> Self {
+        Self {
             snapshot_type,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied version. Pass `None`
+    /// to clear the version
+    pub fn with_version(&self, version: Option<Version>) -> Self {
+        Self {
             version,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied as_of timestamp. Pass
+    /// `None` to clear it
+    pub fn with_as_of(&self, as_of: Option<Timestamp>) -> Self {
+        Self {
+            as_of,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied query parameter set,
+    /// replacing any existing value for the key. The version and as_of are
+    /// set via `with_version` and `with_as_of`; supplying either key here is
+    /// an error, as is a key or value which the parser would not accept
+    pub fn with_query_param(&self, key: &'a str, value: &'a str) -> Result<Self, AmuriError> {
+        check_query_param(key, value)?;
+        let mut model = self.clone();
+        model.query.insert(key, value);
+        Ok(model)
+    }
+
+    /// Return a copy of the model without the supplied query parameter
+    pub fn without_query_param(&self, key: &str) -> Self {
+        let mut model = self.clone();
+        model.query.remove(key);
+        model
+    }
+
+    /// Return a copy of the model with the supplied key. Pass `None`
+    /// to clear the key
+    pub fn with_key(&self, key: Option<&'a str>) -> Self {
+        Self {
             key,
-        ))
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied resource path segments.
+    /// A resource path is only valid in conjunction with a key
+    pub fn with_resource_path<I: IntoIterator<Item = &'a str>>(&self, resource_path: I) -> Self {
+        Self {
+            resource_path: resource_path.into_iter().collect(),
+            ..self.clone()
+        }
+    }
+}
+
+// Verify that the route has the component, so that a model which sets it
+// writes a uri which parses back into the model. The key is not part of the
+// path, and may always be set
+fn check_route_has(
+    scheme: &Scheme,
+    layout: &RouteLayout,
+    component: UriComponent,
+    value: &str,
+) -> Result<(), AmuriError> {
+    if component == UriComponent::Key || layout.contains(component) {
+        return Ok(());
+    }
+    Err(AmuriError::InvalidComponent {
+        component,
+        value: value.into(),
+        reason: format!("is not part of the {} route", scheme),
+    })
+}
+
+// Verify that the component may be absent from a model of the layout
+fn check_optional(layout: &RouteLayout, component: UriComponent) -> Result<(), AmuriError> {
+    let required = match component {
+        UriComponent::Name | UriComponent::SnapshotType => true,
+        UriComponent::Key => false,
+        _ => layout.is_required(component),
+    };
+    if required {
+        Err(AmuriError::MissingComponent(component))
+    } else {
+        Ok(())
     }
 }
-/// Represents the query
-#[derive(Debug, PartialEq, Eq, Clone)]
+
+// Query keys which carry the version and as_of may not be set as other
+// query parameters, as the model would then write them twice
+// verify that a query parameter may be set via with_query_param, and that
+// the uri it is written to parses back into the same parameter
+fn check_query_param(key: &str, value: &str) -> Result<(), AmuriError> {
+    let invalid = |value: &str, reason: &str| AmuriError::InvalidComponent {
+        component: UriComponent::Query,
+        value: value.into(),
+        reason: reason.into(),
+    };
+    match key {
+        "version" => return Err(invalid(key, "version must be set via with_version")),
+        "as_of" => return Err(invalid(key, "as_of must be set via with_as_of")),
+        _ => {}
+    }
+    all_consuming(parse_resource)(key).map_err(|_| {
+        invalid(
+            key,
+            "key must start with a letter, contain only letters, digits and underscores, and not end with an underscore",
+        )
+    })?;
+    all_consuming(parse_query_value)(value).map_err(|_| {
+        invalid(
+            value,
+            "value must contain only letters, digits and underscores",
+        )
+    })?;
+    Ok(())
+}
+
+impl<'a> fmt::Display for AssetModel<'a> {
+    /// Format the model as a canonical uri, writing the path in the order
+    /// of its route layout. Once each required component of the layout is
+    /// set, `parse_uri` will parse the uri back into an equal model
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.container_type, self.level)?;
+        for component in self.layout.components() {
+            if let Some(value) = self.component(*component) {
+                write!(f, "/{}", value)?;
+            }
+        }
+        let mut separator = '?';
+        if let Some(version) = &self.version {
+            write!(f, "{}version={}", separator, version)?;
+            separator = '&';
+        }
+        if let Some(as_of) = self.as_of {
+            write!(f, "{}as_of={}", separator, as_of)?;
+            separator = '&';
+        }
+        for (key, value) in &self.query {
+            write!(f, "{}{}={}", separator, key, value)?;
+            separator = '&';
+        }
+        if let Some(key) = self.key {
+            write!(f, "#{}", key)?;
+        }
+        for segment in &self.resource_path {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents the query. Ordered the same way as `AssetModel`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct OwnedAssetModel {
     container_type: Scheme,
     level: OwnedLevel,
     name: String,
-    department: String,
-    subcontext: String,
+    /// The name of the asset from which an instance derives
+    source: Option<String>,
+    department: Option<String>,
+    subcontext: Option<String>,
+    /// The render layer
+    layer: Option<String>,
+    /// The render pass
+    pass: Option<String>,
     snapshot_type: String,
     version: Option<Version>,
+    /// The point in time at which the version should be resolved
+    as_of: Option<Timestamp>,
+    /// Query parameters other than the version and as_of, ordered by key
+    /// rather than as written
+    query: BTreeMap<String, String>,
     /// The particular filetype we are interested in
     key: Option<String>,
+    /// Segments of the path to an individual resource within the snapshot
+    resource_path: Vec<String>,
+    /// The components of the path
+    layout: RouteLayout,
 }
 
 impl<'a> From<AssetModel<'a>> for OwnedAssetModel {
@@ -90,11 +561,364 @@ impl<'a> From<AssetModel<'a>> for OwnedAssetModel {
             container_type: input.container_type.clone(),
             level: input.level.into(),
             name: input.name.into(),
-            department: input.department.into(),
-            subcontext: input.subcontext.into(),
+            source: input.source.map(str::to_string),
+            department: input.department.map(str::to_string),
+            subcontext: input.subcontext.map(str::to_string),
+            layer: input.layer.map(str::to_string),
+            pass: input.pass.map(str::to_string),
             snapshot_type: input.snapshot_type.into(),
             version: input.version.clone(),
+            as_of: input.as_of,
+            query: input
+                .query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
             key: input.key.map(str::to_string),
+            resource_path: input.resource_path.iter().map(|s| s.to_string()).collect(),
+            layout: input.layout,
+        }
+    }
+}
+
+impl FromStr for OwnedAssetModel {
+    type Err = AmuriError;
+
+    /// Parse a uri into an OwnedAssetModel
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_uri(input).map(OwnedAssetModel::from)
+    }
+}
+
+impl TryFrom<&str> for OwnedAssetModel {
+    type Error = AmuriError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::from_str(input)
+    }
+}
+
+impl TryFrom<String> for OwnedAssetModel {
+    type Error = AmuriError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::from_str(&input)
+    }
+}
+
+impl fmt::Display for OwnedAssetModel {
+    /// Format the model as a canonical uri
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_borrowed().fmt(f)
+    }
+}
+
+impl OwnedAssetModel {
+    /// Borrow the OwnedAssetModel as an AssetModel
+    pub fn as_borrowed(&self) -> AssetModel<'_> {
+        AssetModel {
+            container_type: self.container_type.clone(),
+            level: self.level.as_borrowed(),
+            name: &self.name,
+            source: self.source.as_deref(),
+            department: self.department.as_deref(),
+            subcontext: self.subcontext.as_deref(),
+            layer: self.layer.as_deref(),
+            pass: self.pass.as_deref(),
+            snapshot_type: &self.snapshot_type,
+            version: self.version.clone(),
+            as_of: self.as_of,
+            query: self
+                .query
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+            key: self.key.as_deref(),
+            resource_path: self.resource_path.iter().map(String::as_str).collect(),
+            layout: self.layout.clone(),
+        }
+    }
+
+    /// Retrieve the scheme (container type) of the model
+    pub fn container_type(&self) -> &Scheme {
+        &self.container_type
+    }
+
+    /// Retrieve the level of the model
+    pub fn level(&self) -> &OwnedLevel {
+        &self.level
+    }
+
+    /// Retrieve the container name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Retrieve the name of the asset from which an instance derives, if the
+    /// route has one
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Retrieve the department. This is empty if the route has no
+    /// department (eg a plate); see `component`
+    pub fn department(&self) -> &str {
+        self.department.as_deref().unwrap_or("")
+    }
+
+    /// Retrieve the subcontext. This is empty if the route has no
+    /// subcontext (eg a plate); see `component`
+    pub fn subcontext(&self) -> &str {
+        self.subcontext.as_deref().unwrap_or("")
+    }
+
+    /// Retrieve the render layer, if the route has one
+    pub fn layer(&self) -> Option<&str> {
+        self.layer.as_deref()
+    }
+
+    /// Retrieve the render pass, if the route has one
+    pub fn pass(&self) -> Option<&str> {
+        self.pass.as_deref()
+    }
+
+    /// Retrieve the snapshot type
+    pub fn snapshot_type(&self) -> &str {
+        &self.snapshot_type
+    }
+
+    /// Retrieve the version, if one was specified
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    /// Retrieve the point in time at which the version should be resolved,
+    /// if one was specified
+    pub fn as_of(&self) -> Option<Timestamp> {
+        self.as_of
+    }
+
+    /// Resolve the version against the publish history. See
+    /// `AssetModel::resolve_version`
+    pub fn resolve_version(&self, history: &dyn PublishHistory) -> Result<u32, AmuriError> {
+        self.as_borrowed().resolve_version(history)
+    }
+
+    /// Retrieve the value of a query parameter other than the version and
+    /// as_of. If a uri repeats a parameter, the first value is kept
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query.get(key).map(String::as_str)
+    }
+
+    /// Iterate over the query parameters other than the version and as_of,
+    /// ordered by key. The order in which they were written is not preserved
+    pub fn query_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.query.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Retrieve the key, if one was specified
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Retrieve the value of a component of the path or the key, if the
+    /// model has one. See `AssetModel::component`
+    pub fn component(&self, component: UriComponent) -> Option<&str> {
+        self.as_borrowed().component(component)
+    }
+
+    /// Retrieve the segments of the resource path. This is empty if no
+    /// resource path was specified
+    pub fn resource_path(&self) -> &[String] {
+        &self.resource_path
+    }
+
+    /// Retrieve the layout of the path of the model's route
+    pub fn route_layout(&self) -> &RouteLayout {
+        &self.layout
+    }
+
+    /// Return a copy of the model with the supplied scheme, adopting the
+    /// scheme's route layout. See `AssetModel::with_container_type`
+    pub fn with_container_type(&self, container_type: Scheme) -> Self {
+        self.as_borrowed()
+            .with_container_type(container_type)
+            .into()
+    }
+
+    /// Return a copy of the model following the supplied route layout.
+    /// Components outside of the layout are cleared
+    pub fn with_route_layout(&self, layout: RouteLayout) -> Self {
+        self.as_borrowed().with_route_layout(layout).into()
+    }
+
+    // set or clear a component of the path, without consulting the layout.
+    // The name and snapshot type can not be cleared
+    fn set_component(&mut self, component: UriComponent, value: Option<String>) {
+        match component {
+            UriComponent::Name => {
+                if let Some(value) = value {
+                    self.name = value
+                }
+            }
+            UriComponent::Source => self.source = value,
+            UriComponent::Department => self.department = value,
+            UriComponent::Subcontext => self.subcontext = value,
+            UriComponent::Layer => self.layer = value,
+            UriComponent::Pass => self.pass = value,
+            UriComponent::SnapshotType => {
+                if let Some(value) = value {
+                    self.snapshot_type = value
+                }
+            }
+            UriComponent::Key => self.key = value,
+            _ => {}
+        }
+    }
+
+    /// Return a copy of the model with the supplied value for a component of
+    /// the path or the key. Returns an error if the model's route has no
+    /// such component. See `AssetModel::with_component`
+    pub fn with_component<I: Into<String>>(
+        &self,
+        component: UriComponent,
+        value: I,
+    ) -> Result<Self, AmuriError> {
+        let value = value.into();
+        check_route_has(&self.container_type, &self.layout, component, &value)?;
+        let mut model = self.clone();
+        model.set_component(component, Some(value));
+        Ok(model)
+    }
+
+    /// Return a copy of the model without an optional component of its
+    /// route or the key. Returns an error if the route requires the
+    /// component
+    pub fn without_component(&self, component: UriComponent) -> Result<Self, AmuriError> {
+        check_optional(&self.layout, component)?;
+        let mut model = self.clone();
+        model.set_component(component, None);
+        Ok(model)
+    }
+
+    // set a component of the path, leaving the model unchanged if its route
+    // has no such component
+    fn with_route_component<I: Into<String>>(&self, component: UriComponent, value: I) -> Self {
+        self.with_component(component, value)
+            .unwrap_or_else(|_| self.clone())
+    }
+
+    /// Return a copy of the model with the supplied level
+    pub fn with_level(&self, level: OwnedLevel) -> Self {
+        Self {
+            level,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied container name
+    pub fn with_name<I: Into<String>>(&self, name: I) -> Self {
+        Self {
+            name: name.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied source asset name. This
+    /// has no effect if the route has no source
+    pub fn with_source<I: Into<String>>(&self, source: I) -> Self {
+        self.with_route_component(UriComponent::Source, source)
+    }
+
+    /// Return a copy of the model with the supplied department. This has no
+    /// effect if the route has no department
+    pub fn with_department<I: Into<String>>(&self, department: I) -> Self {
+        self.with_route_component(UriComponent::Department, department)
+    }
+
+    /// Return a copy of the model with the supplied subcontext. This has no
+    /// effect if the route has no subcontext
+    pub fn with_subcontext<I: Into<String>>(&self, subcontext: I) -> Self {
+        self.with_route_component(UriComponent::Subcontext, subcontext)
+    }
+
+    /// Return a copy of the model with the supplied render layer. This has
+    /// no effect if the route has no layer
+    pub fn with_layer<I: Into<String>>(&self, layer: I) -> Self {
+        self.with_route_component(UriComponent::Layer, layer)
+    }
+
+    /// Return a copy of the model with the supplied render pass. This has no
+    /// effect if the route has no pass
+    pub fn with_pass<I: Into<String>>(&self, pass: I) -> Self {
+        self.with_route_component(UriComponent::Pass, pass)
+    }
+
+    /// Return a copy of the model with the supplied snapshot type
+    pub fn with_snapshot_type<I: Into<String>>(&self, snapshot_type: I) -> Self {
+        Self {
+            snapshot_type: snapshot_type.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied version. Pass `None`
+    /// to clear the version
+    pub fn with_version(&self, version: Option<Version>) -> Self {
+        Self {
+            version,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied as_of timestamp. Pass
+    /// `None` to clear it
+    pub fn with_as_of(&self, as_of: Option<Timestamp>) -> Self {
+        Self {
+            as_of,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied query parameter set,
+    /// replacing any existing value for the key. The version and as_of are
+    /// set via `with_version` and `with_as_of`; supplying either key here is
+    /// an error, as is a key or value which the parser would not accept
+    pub fn with_query_param<I: Into<String>>(&self, key: I, value: I) -> Result<Self, AmuriError> {
+        let key = key.into();
+        let value = value.into();
+        check_query_param(&key, &value)?;
+        let mut model = self.clone();
+        model.query.insert(key, value);
+        Ok(model)
+    }
+
+    /// Return a copy of the model without the supplied query parameter
+    pub fn without_query_param(&self, key: &str) -> Self {
+        let mut model = self.clone();
+        model.query.remove(key);
+        model
+    }
+
+    /// Return a copy of the model with the supplied key. Pass `None`
+    /// to clear the key
+    pub fn with_key<I: Into<String>>(&self, key: Option<I>) -> Self {
+        Self {
+            key: key.map(Into::into),
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of the model with the supplied resource path segments.
+    /// A resource path is only valid in conjunction with a key
+    pub fn with_resource_path<I, S>(&self, resource_path: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            resource_path: resource_path.into_iter().map(Into::into).collect(),
+            ..self.clone()
         }
     }
 }
@@ -116,4 +940,383 @@ mod tests {
         );
         assert!(am.is_ok());
     }
+
+    #[test]
+    fn from_strs_validates_components() {
+        let am = AssetModel::from_strs(
+            "asset",
+            "dev01",
+            "Bad Name!",
+            "model",
+            "hi",
+            "alembic_model",
+            None,
+            None,
+        );
+        assert!(am.is_err());
+    }
+
+    fn model() -> AssetModel<'static> {
+        AssetModel::from_strs(
+            "asset",
+            "dev01.rd.9999",
+            "bob",
+            "model",
+            "hi",
+            "alembic_model",
+            Some("current"),
+            Some("main"),
+        )
+        .unwrap()
+    }
+
+    mod accessors {
+        use super::*;
+
+        #[test]
+        fn can_read_components() {
+            let am = model();
+            assert_eq!(am.container_type(), &Scheme::Asset);
+            assert_eq!(am.level(), &Level::shot("dev01", "rd", "9999"));
+            assert_eq!(am.name(), "bob");
+            assert_eq!(am.department(), "model");
+            assert_eq!(am.subcontext(), "hi");
+            assert_eq!(am.snapshot_type(), "alembic_model");
+            assert_eq!(am.version(), Some(&Version::Current));
+            assert_eq!(am.key(), Some("main"));
+        }
+
+        #[test]
+        fn can_read_and_set_components_by_kind() {
+            let am = model().with_container_type(Scheme::Render);
+            let am = am
+                .with_component(UriComponent::Department, "light")
+                .and_then(|am| am.with_component(UriComponent::Layer, "beauty"))
+                .and_then(|am| am.with_component(UriComponent::Pass, "diffuse"))
+                .unwrap();
+            assert_eq!(am.component(UriComponent::Name), Some("bob"));
+            assert_eq!(am.component(UriComponent::Department), Some("light"));
+            assert_eq!(am.component(UriComponent::Layer), Some("beauty"));
+            assert_eq!(am.component(UriComponent::Level), None);
+            assert_eq!(
+                am.to_string(),
+                "render://dev01.rd.9999/bob/light/hi/beauty/diffuse/alembic_model?version=current#main"
+            );
+            let owned = OwnedAssetModel::from(am.clone()).with_pass("specular");
+            assert_eq!(owned.layer(), Some("beauty"));
+            assert_eq!(owned.pass(), Some("specular"));
+            assert_eq!(owned.department(), "light");
+        }
+
+        #[test]
+        fn refuses_components_outside_of_route() {
+            let am = model();
+            assert!(am.with_component(UriComponent::Layer, "beauty").is_err());
+            assert_eq!(am.with_layer("beauty"), am);
+            assert!(am.without_component(UriComponent::Department).is_err());
+            let owned = OwnedAssetModel::from(am);
+            assert!(owned
+                .with_component(UriComponent::Source, "thanos")
+                .is_err());
+            assert_eq!(owned.with_pass("diffuse").pass(), None);
+        }
+
+        #[test]
+        fn plate_ignores_department_and_subcontext() {
+            let am = AssetModel::new(
+                Scheme::Plate,
+                Level::shot("dev01", "rd", "0001"),
+                "bg01",
+                "comp",
+                "hi",
+                "exr",
+                None,
+                None,
+            );
+            assert_eq!(am.department(), "");
+            assert_eq!(am.to_string(), "plate://dev01.rd.0001/bg01/exr");
+            assert_eq!(am.with_department("comp"), am);
+        }
+
+        #[test]
+        fn can_read_components_of_owned_model() {
+            let am = OwnedAssetModel::from(model());
+            assert_eq!(am.container_type(), &Scheme::Asset);
+            assert_eq!(am.level(), &OwnedLevel::shot("dev01", "rd", "9999"));
+            assert_eq!(am.name(), "bob");
+            assert_eq!(am.department(), "model");
+            assert_eq!(am.subcontext(), "hi");
+            assert_eq!(am.snapshot_type(), "alembic_model");
+            assert_eq!(am.version(), Some(&Version::Current));
+            assert_eq!(am.key(), Some("main"));
+        }
+    }
+
+    mod display {
+        use super::*;
+
+        #[test]
+        fn can_display_model() {
+            assert_eq!(
+                model().to_string(),
+                "asset://dev01.rd.9999/bob/model/hi/alembic_model?version=current#main"
+            );
+        }
+
+        #[test]
+        fn can_display_model_without_version_or_key() {
+            let am = model().with_version(None).with_key(None);
+            assert_eq!(
+                am.to_string(),
+                "asset://dev01.rd.9999/bob/model/hi/alembic_model"
+            );
+        }
+
+        #[test]
+        fn owned_model_displays_same_as_model() {
+            let am = model().with_version(Some(Version::number(7)));
+            assert_eq!(
+                OwnedAssetModel::from(am.clone()).to_string(),
+                am.to_string()
+            );
+        }
+    }
+
+    mod query {
+        use super::*;
+
+        #[test]
+        fn can_set_and_read_query_params() {
+            let am = model()
+                .with_query_param("server", "organic")
+                .and_then(|am| am.with_query_param("lod", "high"))
+                .unwrap();
+            assert_eq!(am.query_param("server"), Some("organic"));
+            assert_eq!(am.query_param("missing"), None);
+            assert_eq!(
+                am.query_params().collect::<Vec<_>>(),
+                vec![("lod", "high"), ("server", "organic")]
+            );
+            assert_eq!(am.without_query_param("lod").query_params().count(), 1);
+        }
+
+        #[test]
+        fn query_params_are_displayed_after_version() {
+            let am = model()
+                .with_query_param("server", "organic")
+                .and_then(|am| am.with_query_param("lod", "high"))
+                .unwrap();
+            assert_eq!(
+                am.to_string(),
+                "asset://dev01.rd.9999/bob/model/hi/alembic_model?version=current&lod=high&server=organic#main"
+            );
+            assert_eq!(
+                am.with_version(None).to_string(),
+                "asset://dev01.rd.9999/bob/model/hi/alembic_model?lod=high&server=organic#main"
+            );
+        }
+
+        #[test]
+        fn version_and_as_of_are_not_query_params() {
+            assert_eq!(
+                model().with_query_param("version", "3"),
+                Err(AmuriError::InvalidComponent {
+                    component: UriComponent::Query,
+                    value: "version".into(),
+                    reason: "version must be set via with_version".into()
+                })
+            );
+            let owned = OwnedAssetModel::from(model());
+            assert!(owned.with_query_param("as_of", "2020-03-01").is_err());
+        }
+
+        #[test]
+        fn rejects_query_params_which_would_not_parse() {
+            for (key, value) in &[("a b", "x"), ("", "x"), ("k#x", "x"), ("lod", "x&y=z")] {
+                assert!(matches!(
+                    model().with_query_param(key, value),
+                    Err(AmuriError::InvalidComponent {
+                        component: UriComponent::Query,
+                        ..
+                    })
+                ));
+            }
+        }
+
+        #[test]
+        fn owned_model_rejects_query_params_which_would_not_parse() {
+            let owned = OwnedAssetModel::from(model());
+            for (key, value) in &[("a b", "x"), ("", "x"), ("k#x", "x"), ("lod", "x&y=z")] {
+                assert!(matches!(
+                    owned.with_query_param(*key, *value),
+                    Err(AmuriError::InvalidComponent {
+                        component: UriComponent::Query,
+                        ..
+                    })
+                ));
+            }
+            assert_eq!(
+                owned
+                    .with_query_param("lod", "high")
+                    .unwrap()
+                    .query_param("lod"),
+                Some("high")
+            );
+        }
+
+        #[test]
+        fn query_params_survive_conversion_to_owned() {
+            let am = model().with_query_param("server", "organic").unwrap();
+            let owned = OwnedAssetModel::from(am.clone());
+            assert_eq!(owned.query_param("server"), Some("organic"));
+            assert_eq!(owned.to_string(), am.to_string());
+            assert_eq!(owned.as_borrowed(), am);
+        }
+    }
+
+    mod resource_path {
+        use super::*;
+
+        #[test]
+        fn can_set_and_display_resource_path() {
+            let am = model().with_resource_path(vec!["textures", "diffuse.tx"]);
+            assert_eq!(am.resource_path(), &["textures", "diffuse.tx"]);
+            assert_eq!(
+                am.to_string(),
+                "asset://dev01.rd.9999/bob/model/hi/alembic_model?version=current#main/textures/diffuse.tx"
+            );
+        }
+
+        #[test]
+        fn resource_path_survives_conversion_to_owned() {
+            let am = model().with_resource_path(vec!["diffuse.tx"]);
+            let owned = OwnedAssetModel::from(am.clone());
+            assert_eq!(owned.resource_path(), &["diffuse.tx".to_string()]);
+            assert_eq!(owned.as_borrowed(), am);
+            assert_eq!(
+                owned
+                    .with_resource_path(Vec::<String>::new())
+                    .resource_path()
+                    .len(),
+                0
+            );
+        }
+    }
+
+    mod owned {
+        use super::*;
+
+        #[test]
+        fn can_parse_owned_model_from_str() {
+            let uri = "asset://dev01.rd.9999/bob/model/hi/alembic_model?version=current#main";
+            let expect = OwnedAssetModel::from(model());
+            assert_eq!(uri.parse::<OwnedAssetModel>(), Ok(expect.clone()));
+            assert_eq!(OwnedAssetModel::try_from(uri), Ok(expect.clone()));
+            assert_eq!(OwnedAssetModel::try_from(uri.to_string()), Ok(expect));
+        }
+
+        #[test]
+        fn parsing_owned_model_reports_errors() {
+            assert!(OwnedAssetModel::try_from("asset://dev01/bob").is_err());
+        }
+
+        #[test]
+        fn can_borrow_owned_model() {
+            let owned = OwnedAssetModel::from(model());
+            assert_eq!(owned.as_borrowed(), model());
+        }
+    }
+
+    mod ordering {
+        use super::*;
+        use std::collections::HashSet;
+
+        #[test]
+        fn models_sort_by_components_then_version() {
+            let am = model();
+            let mut models = vec![
+                am.with_version(Some(Version::number(2))),
+                am.with_department("anim"),
+                am.with_container_type(Scheme::Plate),
+                am.with_level(Level::show("dev01")),
+                am.with_version(Some(Version::number(1))),
+            ];
+            models.sort();
+            assert_eq!(
+                models,
+                vec![
+                    am.with_level(Level::show("dev01")),
+                    am.with_department("anim"),
+                    am.with_version(Some(Version::number(1))),
+                    am.with_version(Some(Version::number(2))),
+                    am.with_container_type(Scheme::Plate),
+                ]
+            );
+        }
+
+        #[test]
+        fn models_can_be_hashed() {
+            let mut set = HashSet::new();
+            set.insert(model());
+            set.insert(model());
+            set.insert(model().with_key(None));
+            assert_eq!(set.len(), 2);
+        }
+    }
+
+    mod with {
+        use super::*;
+
+        #[test]
+        fn with_version_leaves_original_untouched() {
+            let am = model();
+            let am2 = am.with_version(Some(Version::number(3)));
+            assert_eq!(am.version(), Some(&Version::Current));
+            assert_eq!(am2.version(), Some(&Version::number(3)));
+            assert_eq!(am2.with_version(Some(Version::Current)), am);
+        }
+
+        #[test]
+        fn can_change_single_component() {
+            let am = model()
+                .with_level(Level::show("dev02"))
+                .with_department("rig")
+                .with_key(None);
+            assert_eq!(am.level(), &Level::show("dev02"));
+            assert_eq!(am.department(), "rig");
+            assert_eq!(am.key(), None);
+            assert_eq!(am.name(), "bob");
+        }
+
+        #[test]
+        fn can_change_single_component_of_owned_model() {
+            let am = OwnedAssetModel::from(model())
+                .with_name("fred")
+                .with_key(Some("alt"));
+            assert_eq!(am.name(), "fred");
+            assert_eq!(am.key(), Some("alt"));
+            assert_eq!(am.department(), "model");
+        }
+    }
+
+    mod resolve_version {
+        use super::*;
+        use crate::history::PublishLog;
+
+        #[test]
+        fn resolves_as_of_the_model_timestamp() {
+            let at = Timestamp::from_secs;
+            let mut log = PublishLog::new();
+            log.publish(1, at(10))
+                .set_current(1, at(10))
+                .publish(2, at(20))
+                .set_current(2, at(20));
+            let am = model().with_as_of(Some(at(15)));
+            assert_eq!(am.resolve_version(&log), Ok(1));
+            assert_eq!(am.with_as_of(Some(at(20))).resolve_version(&log), Ok(2));
+            assert_eq!(am.with_as_of(None).resolve_version(&log), Ok(2));
+            let owned = OwnedAssetModel::from(am.with_version(Some(Version::Latest)));
+            assert_eq!(owned.resolve_version(&log), Ok(1));
+        }
+    }
 }
@@ -0,0 +1,575 @@
+//! Validated construction of an AssetModel
+//!
+//! Each component handed to the builder is run through the same parser
+//! that `parse_uri` uses for it, so a model produced by the builder will
+//! always survive a round trip through its uri.
+use crate::assetmodel::AssetModel;
+use crate::errors::{AmuriError, UriComponent};
+use crate::level_schema::LevelSchemas;
+use crate::parse::{
+    container_name::parse_name_noslash, query::parse_query_value, resource::parse_resource,
+    resource_path::parse_path_segment,
+};
+use crate::route::PATH_COMPONENTS;
+use crate::scheme::Scheme;
+use crate::scheme_registry::SchemeRegistry;
+use crate::timestamp::Timestamp;
+use crate::version::Version;
+use nom::{combinator::all_consuming, IResult};
+use std::str::FromStr;
+
+const NAME_RULE: &str =
+    "must start with a letter, contain only letters, digits and underscores, and not end with an underscore";
+const LEVEL_RULE: &str =
+    "must take the form show, show.seq or show.seq.shot, or as the show's level schema describes";
+const VERSION_RULE: &str = "must be read back as the same version";
+const QUERY_VALUE_RULE: &str = "must contain only letters, digits and underscores";
+const QUERY_VERSION_RULE: &str = "version must be set via the version method";
+const QUERY_AS_OF_RULE: &str = "as_of must be set via the as_of method";
+const SEGMENT_RULE: &str =
+    "must start with a letter or digit, and contain only letters, digits, underscores, hyphens and periods";
+
+/// Builder for an AssetModel which validates each component as it is built
+#[derive(Debug, Default, Clone)]
+pub struct AssetModelBuilder<'a> {
+    container_type: Option<Scheme>,
+    level: Option<&'a str>,
+    level_schemas: LevelSchemas,
+    schemes: SchemeRegistry,
+    name: Option<&'a str>,
+    source: Option<&'a str>,
+    department: Option<&'a str>,
+    subcontext: Option<&'a str>,
+    layer: Option<&'a str>,
+    pass: Option<&'a str>,
+    snapshot_type: Option<&'a str>,
+    version: Option<Version>,
+    as_of: Option<Timestamp>,
+    query: Vec<(&'a str, &'a str)>,
+    key: Option<&'a str>,
+    resource_path: Vec<&'a str>,
+}
+
+impl<'a> AssetModelBuilder<'a> {
+    /// constructor function
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the scheme (container type)
+    pub fn container_type(mut self, container_type: Scheme) -> Self {
+        self.container_type = Some(container_type);
+        self
+    }
+
+    /// Set the level from a levelspec str (eg show.seq.shot)
+    pub fn level(mut self, level: &'a str) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Set the level schema of each show, which the level must follow. By
+    /// default, every show uses show.sequence.shot
+    pub fn level_schemas(mut self, level_schemas: LevelSchemas) -> Self {
+        self.level_schemas = level_schemas;
+        self
+    }
+
+    /// Set the registry of schemes, which supplies the name rule, route
+    /// layout and required components of the scheme, along with the asset
+    /// name rule which the source of an instance follows. By default, only
+    /// the built-in schemes are registered
+    pub fn schemes(mut self, schemes: SchemeRegistry) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    /// Set the container name
+    pub fn name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Set the name of the source asset, for schemes whose route has one (eg
+    /// instance). The source must follow the name rule of the asset scheme
+    pub fn source(mut self, source: &'a str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Set the department
+    pub fn department(mut self, department: &'a str) -> Self {
+        self.department = Some(department);
+        self
+    }
+
+    /// Set the subcontext
+    pub fn subcontext(mut self, subcontext: &'a str) -> Self {
+        self.subcontext = Some(subcontext);
+        self
+    }
+
+    /// Set the render layer, for schemes whose route has one (eg render)
+    pub fn layer(mut self, layer: &'a str) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// Set the render pass, for schemes whose route has one (eg render)
+    pub fn pass(mut self, pass: &'a str) -> Self {
+        self.pass = Some(pass);
+        self
+    }
+
+    /// Set the snapshot type
+    pub fn snapshot_type(mut self, snapshot_type: &'a str) -> Self {
+        self.snapshot_type = Some(snapshot_type);
+        self
+    }
+
+    /// Set the version. The version is optional
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Set the point in time at which the version should be resolved. This
+    /// is optional
+    pub fn as_of(mut self, as_of: Timestamp) -> Self {
+        self.as_of = Some(as_of);
+        self
+    }
+
+    /// Add a query parameter other than the version and as_of. Query
+    /// parameters are optional
+    pub fn query_param(mut self, key: &'a str, value: &'a str) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Set the key. The key is optional
+    pub fn key(mut self, key: &'a str) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Append a segment to the resource path. The resource path is optional,
+    /// but requires a key
+    pub fn resource_path_segment(mut self, segment: &'a str) -> Self {
+        self.resource_path.push(segment);
+        self
+    }
+
+    // the value set for a component of the path
+    fn path_component(&self, component: UriComponent) -> Option<&'a str> {
+        match component {
+            UriComponent::Name => self.name,
+            UriComponent::Source => self.source,
+            UriComponent::Department => self.department,
+            UriComponent::Subcontext => self.subcontext,
+            UriComponent::Layer => self.layer,
+            UriComponent::Pass => self.pass,
+            UriComponent::SnapshotType => self.snapshot_type,
+            _ => None,
+        }
+    }
+
+    /// Validate each component and build the AssetModel. Components are checked
+    /// in uri order, and the first failure is reported. Each component of the
+    /// scheme's route layout is required unless the layout makes it optional,
+    /// and components outside of the layout are rejected.
+    pub fn build(self) -> Result<AssetModel<'a>, AmuriError> {
+        let container_type = self
+            .container_type
+            .clone()
+            .ok_or(AmuriError::MissingComponent(UriComponent::Scheme))?;
+        let level = required(UriComponent::Level, self.level)?;
+        let level = self
+            .level_schemas
+            .parse_level(level)
+            .map_err(|_| invalid(UriComponent::Level, level, LEVEL_RULE))?;
+        let name = validate_name(UriComponent::Name, self.name)?;
+        self.schemes.validate_name(&container_type, name)?;
+        let layout = self.schemes.layout(&container_type);
+        let mut route = Vec::new();
+        // the layout follows the order of PATH_COMPONENTS, starting with the name
+        for component in PATH_COMPONENTS.iter().skip(1).copied() {
+            let value = self.path_component(component);
+            if !layout.contains(component) {
+                if let Some(value) = value {
+                    let reason = format!("is not part of the {} route", container_type);
+                    return Err(invalid(component, value, &reason));
+                }
+                continue;
+            }
+            if value.is_none() && layout.is_optional(component) {
+                continue;
+            }
+            let value = validate_name(component, value)?;
+            if component == UriComponent::Source {
+                let rule = self.schemes.name_rule(&Scheme::Asset);
+                if !rule.matches(value) {
+                    return Err(invalid(component, value, rule.description()));
+                }
+            }
+            route.push((component, value));
+        }
+        let snapshot_type = route.last().map_or("", |(_, value)| *value);
+        if let Some(version) = &self.version {
+            // a tag constructed directly may be read back as another version
+            if Version::from_str(&version.to_string()).as_ref() != Ok(version) {
+                return Err(invalid(
+                    UriComponent::Version,
+                    &version.to_string(),
+                    VERSION_RULE,
+                ));
+            }
+        }
+        let key = self
+            .key
+            .map(|key| validate(UriComponent::Key, key, parse_resource, NAME_RULE))
+            .transpose()?;
+        if key.is_none() && !self.resource_path.is_empty() {
+            return Err(AmuriError::MissingComponent(UriComponent::Key));
+        }
+        let resource_path = self
+            .resource_path
+            .into_iter()
+            .map(|segment| {
+                validate(
+                    UriComponent::ResourcePath,
+                    segment,
+                    parse_path_segment,
+                    SEGMENT_RULE,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut model = AssetModel::minimal(container_type, level, name, snapshot_type)
+            .with_route_layout(layout)
+            .with_version(self.version)
+            .with_as_of(self.as_of)
+            .with_key(key)
+            .with_resource_path(resource_path);
+        for (component, value) in route {
+            model = model.with_component(component, value)?;
+        }
+        for (key, value) in self.query {
+            if key == "version" {
+                return Err(invalid(UriComponent::Query, key, QUERY_VERSION_RULE));
+            }
+            if key == "as_of" {
+                return Err(invalid(UriComponent::Query, key, QUERY_AS_OF_RULE));
+            }
+            let key = validate(UriComponent::Query, key, parse_resource, NAME_RULE)?;
+            let value = validate(
+                UriComponent::Query,
+                value,
+                parse_query_value,
+                QUERY_VALUE_RULE,
+            )?;
+            model = model.with_query_param(key, value)?;
+        }
+        self.schemes.check_required(&model)?;
+        Ok(model)
+    }
+}
+
+fn invalid(component: UriComponent, value: &str, reason: &str) -> AmuriError {
+    AmuriError::InvalidComponent {
+        component,
+        value: value.into(),
+        reason: reason.into(),
+    }
+}
+
+fn required(component: UriComponent, value: Option<&str>) -> Result<&str, AmuriError> {
+    value.ok_or(AmuriError::MissingComponent(component))
+}
+
+// run the value through the supplied parser, requiring that it consume all of it
+fn validate<'a>(
+    component: UriComponent,
+    value: &'a str,
+    parser: fn(&str) -> IResult<&str, &str>,
+    reason: &str,
+) -> Result<&'a str, AmuriError> {
+    all_consuming(parser)(value)
+        .map(|(_, r)| r)
+        .map_err(|_| invalid(component, value, reason))
+}
+
+fn validate_name(component: UriComponent, value: Option<&str>) -> Result<&str, AmuriError> {
+    validate(
+        component,
+        required(component, value)?,
+        parse_name_noslash,
+        NAME_RULE,
+    )
+}
+
+impl<'a> AssetModel<'a> {
+    /// Retrieve a builder for an AssetModel
+    pub fn builder() -> AssetModelBuilder<'a> {
+        AssetModelBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::parse::container_name::NameRule;
+
+    fn builder() -> AssetModelBuilder<'static> {
+        AssetModel::builder()
+            .container_type(Scheme::Asset)
+            .level("dev01.rd")
+            .name("bob")
+            .department("model")
+            .subcontext("hi")
+            .snapshot_type("alembic_model")
+    }
+
+    #[test]
+    fn can_build_model() {
+        let model = builder().version(Version::Current).key("main").build();
+        assert_eq!(
+            model,
+            Ok(AssetModel::new(
+                Scheme::Asset,
+                Level::seq("dev01", "rd"),
+                "bob",
+                "model",
+                "hi",
+                "alembic_model",
+                Some(Version::Current),
+                Some("main"),
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_name() {
+        assert_eq!(
+            builder().name("Bad Name!").build(),
+            Err(invalid(UriComponent::Name, "Bad Name!", NAME_RULE))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_level() {
+        assert_eq!(
+            builder().level("dev01.rd.").build(),
+            Err(invalid(UriComponent::Level, "dev01.rd.", LEVEL_RULE))
+        );
+    }
+
+    #[test]
+    fn level_follows_show_schema() {
+        use crate::level_schema::LevelSchema;
+        let schemas = LevelSchemas::new().with_show(
+            "dev02",
+            LevelSchema::new(vec!["show", "episode", "sequence", "shot"]).unwrap(),
+        );
+        let model = builder()
+            .level_schemas(schemas.clone())
+            .level("dev02.ep01.rd.0001")
+            .build()
+            .unwrap();
+        assert_eq!(model.level().depth(), 4);
+        assert_eq!(
+            builder()
+                .level_schemas(schemas)
+                .level("dev01.ep01.rd.0001")
+                .build(),
+            Err(invalid(
+                UriComponent::Level,
+                "dev01.ep01.rd.0001",
+                LEVEL_RULE
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_scheme_name_rule_violation() {
+        assert_eq!(
+            builder().container_type(Scheme::Instance).build(),
+            Err(AmuriError::NameRuleViolation {
+                scheme: Scheme::Instance,
+                name: "bob".into(),
+                rule: "name must end with a digit"
+            })
+        );
+    }
+
+    #[test]
+    fn follows_registered_scheme() {
+        use crate::scheme_registry::SchemeDefinition;
+        let schemes = SchemeRegistry::new()
+            .with_scheme(
+                SchemeDefinition::new("texture")
+                    .unwrap()
+                    .with_required(UriComponent::Key),
+            )
+            .unwrap();
+        let builder = builder()
+            .schemes(schemes)
+            .container_type(Scheme::named("texture"));
+        assert_eq!(
+            builder.clone().build(),
+            Err(AmuriError::MissingComponent(UriComponent::Key))
+        );
+        let model = builder.key("main").build().unwrap();
+        assert_eq!(
+            model.to_string(),
+            "texture://dev01.rd/bob/model/hi/alembic_model#main"
+        );
+    }
+
+    #[test]
+    fn follows_route_layout() {
+        let model = builder()
+            .container_type(Scheme::Render)
+            .layer("beauty")
+            .pass("diffuse")
+            .build()
+            .unwrap();
+        assert_eq!(
+            model.to_string(),
+            "render://dev01.rd/bob/model/hi/beauty/diffuse/alembic_model"
+        );
+        let model = AssetModel::builder()
+            .container_type(Scheme::Plate)
+            .level("dev01.rd")
+            .name("bg01")
+            .snapshot_type("exr")
+            .build()
+            .unwrap();
+        assert_eq!(model.to_string(), "plate://dev01.rd/bg01/exr");
+    }
+
+    #[test]
+    fn reports_components_outside_of_route_layout() {
+        assert_eq!(
+            builder().container_type(Scheme::Plate).name("bg01").build(),
+            Err(invalid(
+                UriComponent::Department,
+                "model",
+                "is not part of the plate route"
+            ))
+        );
+        let instance = builder().container_type(Scheme::Instance).name("bob1");
+        assert_eq!(
+            instance.clone().build().map(|m| m.to_string()),
+            Ok("instance://dev01.rd/bob1/model/hi/alembic_model".to_string())
+        );
+        assert_eq!(
+            instance.clone().source("bob2").build(),
+            Err(invalid(
+                UriComponent::Source,
+                "bob2",
+                NameRule::NoTrailingDigit.description()
+            ))
+        );
+        assert_eq!(
+            instance.source("bob").build().map(|m| m.to_string()),
+            Ok("instance://dev01.rd/bob1/bob/model/hi/alembic_model".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_version_which_would_not_roundtrip() {
+        assert_eq!(
+            builder().version(Version::Tag("latest".into())).build(),
+            Err(invalid(UriComponent::Version, "latest", VERSION_RULE))
+        );
+        assert!(builder()
+            .version(Version::tag("approved").unwrap())
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn reports_invalid_key() {
+        assert_eq!(
+            builder().key("main_").build(),
+            Err(invalid(UriComponent::Key, "main_", NAME_RULE))
+        );
+    }
+
+    #[test]
+    fn can_build_model_with_query_params() {
+        let model = builder().query_param("server", "organic").build().unwrap();
+        assert_eq!(model.query_param("server"), Some("organic"));
+    }
+
+    #[test]
+    fn can_build_model_with_as_of() {
+        let as_of = Timestamp::from_secs(1_583_020_800);
+        let model = builder().as_of(as_of).build().unwrap();
+        assert_eq!(model.as_of(), Some(as_of));
+    }
+
+    #[test]
+    fn reports_invalid_query_param() {
+        assert_eq!(
+            builder().query_param("server", "organic!").build(),
+            Err(invalid(UriComponent::Query, "organic!", QUERY_VALUE_RULE))
+        );
+        assert_eq!(
+            builder().query_param("version", "1").build(),
+            Err(invalid(UriComponent::Query, "version", QUERY_VERSION_RULE))
+        );
+        assert_eq!(
+            builder().query_param("as_of", "2020-03-01").build(),
+            Err(invalid(UriComponent::Query, "as_of", QUERY_AS_OF_RULE))
+        );
+    }
+
+    #[test]
+    fn can_build_model_with_resource_path() {
+        let model = builder()
+            .key("main")
+            .resource_path_segment("textures")
+            .resource_path_segment("diffuse.tx")
+            .build()
+            .unwrap();
+        assert_eq!(model.resource_path(), &["textures", "diffuse.tx"]);
+    }
+
+    #[test]
+    fn resource_path_requires_key() {
+        assert_eq!(
+            builder().resource_path_segment("diffuse.tx").build(),
+            Err(AmuriError::MissingComponent(UriComponent::Key))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_resource_path_segment() {
+        assert_eq!(
+            builder().key("main").resource_path_segment("..").build(),
+            Err(invalid(UriComponent::ResourcePath, "..", SEGMENT_RULE))
+        );
+    }
+
+    #[test]
+    fn reports_missing_component() {
+        let builder = AssetModelBuilder::new()
+            .container_type(Scheme::Asset)
+            .level("dev01")
+            .name("bob");
+        assert_eq!(
+            builder.build(),
+            Err(AmuriError::MissingComponent(UriComponent::Department))
+        );
+    }
+
+    #[test]
+    fn reports_first_invalid_component() {
+        assert_eq!(
+            builder().department("1model").subcontext("_hi").build(),
+            Err(invalid(UriComponent::Department, "1model", NAME_RULE))
+        );
+    }
+}
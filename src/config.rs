@@ -0,0 +1,74 @@
+//! Configuration for parsing uris
+//!
+//! A ParseConfig gathers the settings which tailor `parse_uri_with_config`
+//! to a studio: the vocabulary of known component values, the level schema
+//! of each show, and the registry of known schemes. A strict config, intended
+//! for automated tooling, rejects scheme aliases.
+use crate::level_schema::LevelSchemas;
+use crate::scheme_registry::SchemeRegistry;
+use crate::vocabulary::Vocabulary;
+
+/// Settings which govern how a uri is parsed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseConfig {
+    vocabulary: Vocabulary,
+    level_schemas: LevelSchemas,
+    schemes: SchemeRegistry,
+    strict: bool,
+}
+
+impl ParseConfig {
+    /// constructor function. The resulting config places no restrictions on
+    /// components, uses the default level schema for every show, and knows
+    /// only the built-in schemes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the vocabulary of known department, subcontext and snapshot type
+    /// values
+    pub fn with_vocabulary(mut self, vocabulary: Vocabulary) -> Self {
+        self.vocabulary = vocabulary;
+        self
+    }
+
+    /// Set the level schema of each show
+    pub fn with_level_schemas(mut self, level_schemas: LevelSchemas) -> Self {
+        self.level_schemas = level_schemas;
+        self
+    }
+
+    /// Set the registry of schemes which may appear in a uri
+    pub fn with_schemes(mut self, schemes: SchemeRegistry) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    /// Set whether parsing is strict. A strict parse accepts only the
+    /// canonical name of a scheme followed by ://, rejecting aliases (eg
+    /// inst) and the single slash form of the separator (eg asset:/)
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Retrieve the vocabulary
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Retrieve the level schemas
+    pub fn level_schemas(&self) -> &LevelSchemas {
+        &self.level_schemas
+    }
+
+    /// Retrieve the scheme registry
+    pub fn schemes(&self) -> &SchemeRegistry {
+        &self.schemes
+    }
+
+    /// Test whether parsing is strict
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+}
@@ -0,0 +1,276 @@
+//! Descriptions of schemes, for building user interfaces
+//!
+//! A SchemeDescription reports, as data, what a uri of a given scheme is
+//! made of: its components in uri order and whether each is required, the
+//! query keys it accepts, the rule its container names follow, and the level
+//! depths its shows permit. Descriptions are built from the same definitions
+//! that `parse_uri_with_config` consults (the scheme registry, level schemas
+//! and vocabulary of a ParseConfig), so a form built from one accepts exactly
+//! what the parser does.
+use crate::config::ParseConfig;
+use crate::errors::UriComponent;
+use crate::level_schema::LevelSchema;
+use crate::parse::{container_name::NameRule, query::QueryKey, uri::expected};
+use crate::scheme::Scheme;
+use crate::scheme_registry::SchemeDefinition;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Describes a component of a uri
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ComponentDescription {
+    /// The component
+    pub component: UriComponent,
+    /// Whether a uri must supply the component
+    pub required: bool,
+    /// Description of the text the parser expects for the component
+    pub expected: &'static str,
+    /// The values which the component may take. Empty if the component is
+    /// unrestricted
+    pub known_values: Vec<String>,
+}
+
+/// Describes a query key which the parser treats specially
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryKeyDescription {
+    /// The key (eg version)
+    pub key: &'static str,
+    /// Whether a uri must supply the key
+    pub required: bool,
+    /// Description of the value the parser expects for the key
+    pub expected: &'static str,
+}
+
+/// Describes the uris of a scheme
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SchemeDescription {
+    /// The scheme
+    pub scheme: Scheme,
+    /// The aliases by which the scheme may also be written, unless parsing
+    /// is strict
+    pub aliases: Vec<String>,
+    /// The rule which container names must follow
+    pub name_rule: NameRule,
+    /// The components of the uri, in uri order
+    pub components: Vec<ComponentDescription>,
+    /// The query keys which the parser treats specially
+    pub query_keys: Vec<QueryKeyDescription>,
+    /// Whether query keys other than those in `query_keys` are accepted
+    pub other_query_keys: bool,
+    /// The level schema of shows without a schema of their own
+    pub level_schema: LevelSchema,
+    /// The level schema of each show with a schema of its own
+    pub show_level_schemas: BTreeMap<String, LevelSchema>,
+}
+
+impl SchemeDescription {
+    /// Describe the scheme as parsed under the supplied config
+    pub(crate) fn new(config: &ParseConfig, definition: &SchemeDefinition) -> Self {
+        use UriComponent::*;
+        let requires = |component| definition.required().contains(&component);
+        let vocabulary = config.vocabulary();
+        let layout = definition.layout();
+        let mut components = vec![Scheme, Level]
+            .into_iter()
+            .chain(layout.components().iter().copied())
+            .map(|component| ComponentDescription {
+                component,
+                required: !layout.is_optional(component),
+                expected: expected(component),
+                known_values: match component {
+                    Department => vocabulary.departments().to_vec(),
+                    Subcontext => vocabulary.subcontexts().to_vec(),
+                    SnapshotType => vocabulary.snapshot_types().to_vec(),
+                    _ => Vec::new(),
+                },
+            })
+            .collect::<Vec<_>>();
+        components.extend([Query, Key, ResourcePath].iter().map(|component| {
+            ComponentDescription {
+                component: *component,
+                required: requires(*component),
+                expected: expected(*component),
+                known_values: Vec::new(),
+            }
+        }));
+        let schemes = config.schemes();
+        let level_schemas = config.level_schemas();
+        Self {
+            scheme: definition.scheme().clone(),
+            aliases: schemes
+                .aliases()
+                .filter(|(_, canonical)| *canonical == definition.name())
+                .map(|(alias, _)| alias.to_string())
+                .collect(),
+            name_rule: definition.name_rule(),
+            components,
+            query_keys: QueryKey::ALL
+                .iter()
+                .map(|key| QueryKeyDescription {
+                    key: key.key(),
+                    required: requires(key.component()),
+                    expected: key.expected(),
+                })
+                .collect(),
+            other_query_keys: QueryKey::OTHERS_ACCEPTED,
+            level_schema: level_schemas.default_schema().clone(),
+            show_level_schemas: level_schemas
+                .shows()
+                .map(|(show, schema)| (show.to_string(), schema.clone()))
+                .collect(),
+        }
+    }
+
+    /// Retrieve the description of a component, if the scheme's uris have it
+    pub fn component(&self, component: UriComponent) -> Option<&ComponentDescription> {
+        self.components.iter().find(|c| c.component == component)
+    }
+
+    /// Retrieve the level schema of a show
+    pub fn level_schema_for(&self, show: &str) -> &LevelSchema {
+        self.show_level_schemas
+            .get(show)
+            .unwrap_or(&self.level_schema)
+    }
+
+    /// The number of tiers a level of the show may have (eg 1..=3 for show,
+    /// show.seq and show.seq.shot)
+    pub fn level_depths(&self, show: &str) -> RangeInclusive<usize> {
+        1..=self.level_schema_for(show).depth()
+    }
+}
+
+impl ParseConfig {
+    /// Describe the uris of a registered scheme, as parsed under the config.
+    /// Returns None if the scheme is not registered
+    pub fn describe(&self, scheme: &Scheme) -> Option<SchemeDescription> {
+        self.schemes()
+            .definition(scheme)
+            .map(|definition| SchemeDescription::new(self, definition))
+    }
+
+    /// Describe the uris of each registered scheme, in alphabetical order of
+    /// scheme name
+    pub fn describe_schemes(&self) -> Vec<SchemeDescription> {
+        self.schemes()
+            .names()
+            .filter_map(|name| self.schemes().get(name))
+            .map(|definition| SchemeDescription::new(self, definition))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_schema::LevelSchemas;
+    use crate::scheme_registry::SchemeRegistry;
+    use crate::vocabulary::Vocabulary;
+
+    fn components(description: &SchemeDescription) -> Vec<String> {
+        description
+            .components
+            .iter()
+            .map(|c| c.component.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn describes_components_in_uri_order() {
+        let description = ParseConfig::new().describe(&Scheme::Render).unwrap();
+        assert_eq!(
+            components(&description),
+            vec![
+                "scheme",
+                "level",
+                "name",
+                "department",
+                "subcontext",
+                "layer",
+                "pass",
+                "snapshot type",
+                "query",
+                "key",
+                "resource path"
+            ]
+        );
+        assert_eq!(description.aliases, vec!["rnd".to_string()]);
+        assert_eq!(description.name_rule, NameRule::Any);
+        let plate = ParseConfig::new().describe(&Scheme::Plate).unwrap();
+        assert!(plate.component(UriComponent::Department).is_none());
+        let instance = ParseConfig::new().describe(&Scheme::Instance).unwrap();
+        assert_eq!(
+            instance.name_rule.description(),
+            "name must end with a digit"
+        );
+        assert!(!instance.component(UriComponent::Source).unwrap().required);
+        assert!(
+            instance
+                .component(UriComponent::Department)
+                .unwrap()
+                .required
+        );
+    }
+
+    #[test]
+    fn describes_required_components_and_query_keys() {
+        let config = ParseConfig::new().with_schemes(
+            SchemeRegistry::new()
+                .with_scheme(
+                    SchemeDefinition::from(Scheme::Asset)
+                        .with_required(UriComponent::Version)
+                        .with_required(UriComponent::Key),
+                )
+                .unwrap(),
+        );
+        let description = config.describe(&Scheme::Asset).unwrap();
+        assert!(description.component(UriComponent::Name).unwrap().required);
+        assert!(description.component(UriComponent::Key).unwrap().required);
+        assert!(!description.component(UriComponent::Query).unwrap().required);
+        assert_eq!(
+            description
+                .query_keys
+                .iter()
+                .map(|k| (k.key, k.required))
+                .collect::<Vec<_>>(),
+            vec![("version", true), ("as_of", false)]
+        );
+        assert!(description.other_query_keys);
+    }
+
+    #[test]
+    fn describes_known_values_and_level_depths() {
+        let config = ParseConfig::new()
+            .with_vocabulary(Vocabulary::new().with_departments(vec!["model", "anim"]))
+            .with_level_schemas(LevelSchemas::new().with_show(
+                "dev02",
+                LevelSchema::new(vec!["show", "episode", "sequence", "shot"]).unwrap(),
+            ));
+        let description = config.describe(&Scheme::Asset).unwrap();
+        let department = description.component(UriComponent::Department).unwrap();
+        assert_eq!(department.known_values, vec!["model", "anim"]);
+        assert_eq!(department.expected, expected(UriComponent::Department));
+        assert_eq!(description.level_depths("dev01"), 1..=3);
+        assert_eq!(description.level_depths("dev02"), 1..=4);
+    }
+
+    #[test]
+    fn describes_each_registered_scheme() {
+        let config = ParseConfig::new().with_schemes(
+            SchemeRegistry::new()
+                .with_scheme(SchemeDefinition::new("texture").unwrap())
+                .unwrap(),
+        );
+        assert_eq!(
+            config
+                .describe_schemes()
+                .iter()
+                .map(|d| d.scheme.to_string())
+                .collect::<Vec<_>>(),
+            vec!["asset", "instance", "plate", "render", "texture"]
+        );
+        assert!(ParseConfig::new()
+            .describe(&Scheme::named("texture"))
+            .is_none());
+    }
+}
@@ -1,11 +1,173 @@
+use crate::scheme::Scheme;
+use crate::version::Version;
+use std::fmt;
 use strum;
+use strum_macros::Display;
 use thiserror::Error; //ParseError
+
+/// The components of a uri, used to report which one was at fault
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[strum(serialize_all = "snake_case")]
+pub enum UriComponent {
+    Scheme,
+    Level,
+    Name,
+    Source,
+    Department,
+    Subcontext,
+    Layer,
+    Pass,
+    #[strum(serialize = "snapshot type")]
+    SnapshotType,
+    Version,
+    AsOf,
+    Query,
+    Key,
+    #[strum(serialize = "resource path")]
+    ResourcePath,
+}
+
+/// Describes where and why a uri failed to parse
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseDiagnostic {
+    /// The uri which failed to parse
+    pub input: String,
+    /// Byte offset into the input at which parsing failed
+    pub offset: usize,
+    /// The component of the uri being parsed when the failure occurred
+    pub component: UriComponent,
+    /// Description of what the parser expected to find
+    pub expected: &'static str,
+    /// The text found at the offset, or "end of uri"
+    pub found: String,
+    /// Known values close to the text supplied for the component
+    pub suggestions: Vec<String>,
+    /// Further detail about the failure, such as the rule which a name broke
+    pub note: Option<Box<str>>,
+}
+
+impl ParseDiagnostic {
+    /// constructor function. The found text is extracted from the input,
+    /// starting at the offset and running up to the next delimiter. An offset
+    /// beyond the end of the input, or within a character, is moved back to
+    /// the nearest character boundary
+    pub fn new<I: Into<String>>(
+        input: I,
+        offset: usize,
+        component: UriComponent,
+        expected: &'static str,
+    ) -> Self {
+        let input = input.into();
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let rest = &input[offset..];
+        let found = match rest.find(|c| "/?&#:".contains(c)) {
+            _ if rest.is_empty() => "end of uri".to_string(),
+            Some(0) => rest[..1].to_string(),
+            Some(end) => rest[..end].to_string(),
+            None => rest.to_string(),
+        };
+        Self {
+            input,
+            offset,
+            component,
+            expected,
+            found,
+            suggestions: Vec::new(),
+            note: None,
+        }
+    }
+
+    /// Attach "did you mean" suggestions to the diagnostic
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    /// Attach further detail about the failure to the diagnostic
+    pub fn with_note<I: Into<String>>(mut self, note: I) -> Self {
+        self.note = Some(note.into().into_boxed_str());
+        self
+    }
+
+    // the suggestions formatted as 'a', 'b' or 'c'
+    fn did_you_mean(&self) -> Option<String> {
+        let quoted = self
+            .suggestions
+            .iter()
+            .map(|s| format!("'{}'", s))
+            .collect::<Vec<_>>();
+        match quoted.split_last() {
+            None => None,
+            Some((last, [])) => Some(format!("did you mean {}?", last)),
+            Some((last, rest)) => Some(format!("did you mean {} or {}?", rest.join(", "), last)),
+        }
+    }
+
+    /// Render a multi-line message with the offending text underlined
+    /// with carets. eg
+    /// ```text
+    /// invalid scheme: expected ...
+    ///   assetf://dev01/bob/model/hi/maya_model
+    ///        ^
+    /// ```
+    pub fn render(&self) -> String {
+        let column = self
+            .input
+            .char_indices()
+            .take_while(|(idx, _)| *idx < self.offset)
+            .count();
+        let width = if self.offset >= self.input.len() {
+            1
+        } else {
+            self.found.chars().count().max(1)
+        };
+        let mut message = format!(
+            "invalid {}: expected {}, found '{}'\n  {}\n  {}{}",
+            self.component,
+            self.expected,
+            self.found,
+            self.input,
+            " ".repeat(column),
+            "^".repeat(width)
+        );
+        if let Some(did_you_mean) = self.did_you_mean() {
+            message.push(' ');
+            message.push_str(&did_you_mean);
+        }
+        if let Some(note) = &self.note {
+            message.push_str("\n  ");
+            message.push_str(note);
+        }
+        message
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} at offset {}: expected {}, found '{}'",
+            self.component, self.offset, self.expected, self.found
+        )?;
+        if let Some(did_you_mean) = self.did_you_mean() {
+            write!(f, "; {}", did_you_mean)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, "; {}", note)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum AmuriError {
     #[error("Unable to convert str {target} to int")]
     StrToIntError { target: String },
-    #[error("Error parsing uri {cause:?}")]
-    UriParsingError { cause: String },
+    #[error("Error parsing uri: {0}")]
+    UriParsingError(ParseDiagnostic),
     #[error("Error parsing scheme from str {cause:?}")]
     SchemeParsingError {
         #[from]
@@ -13,4 +175,133 @@ pub enum AmuriError {
     },
     #[error("Failed to parse level from {0}")]
     LevelParsingFailure(String),
+    #[error("Invalid {component} '{value}': {reason}")]
+    InvalidComponent {
+        component: UriComponent,
+        value: String,
+        reason: String,
+    },
+    #[error("Missing required {0}")]
+    MissingComponent(UriComponent),
+    #[error("Invalid {scheme} name '{name}': {rule}")]
+    NameRuleViolation {
+        scheme: Scheme,
+        name: String,
+        rule: &'static str,
+    },
+    #[error("No version matching {version} is available")]
+    VersionNotFound { version: Version },
+    #[error("The current version is not set")]
+    CurrentVersionUnset,
+    #[error("Failed to parse version constraint from {0}")]
+    VersionReqParsingFailure(String),
+    #[error("Invalid version tag {0}")]
+    InvalidVersionTag(String),
+    #[error("The tag {0} is not bound to a version")]
+    TagUnbound(String),
+    #[error("Failed to parse timestamp from {0}")]
+    TimestampParsingFailure(String),
+    #[error("The level set {set} holds {size} levels, more than the limit of {limit}")]
+    LevelSetTooLarge { set: String, size: u64, limit: u64 },
+    #[error("The scheme {0} is not registered")]
+    SchemeNotRegistered(Scheme),
+    #[error("The name {0} can not be both a scheme and an alias")]
+    AliasConflict(String),
+    #[error("Invalid level schema: {0}")]
+    InvalidLevelSchema(String),
+    #[error("Invalid route layout: {0}")]
+    InvalidRouteLayout(String),
+    #[error("Invalid pattern '{pattern}': {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+}
+
+impl AmuriError {
+    /// Retrieve any "did you mean" suggestions associated with the error.
+    /// This is empty for errors which carry no suggestions
+    pub fn suggestions(&self) -> &[String] {
+        match self {
+            Self::UriParsingError(diagnostic) => &diagnostic.suggestions,
+            _ => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_diagnostic {
+        use super::*;
+
+        #[test]
+        fn found_runs_to_next_delimiter() {
+            let diag =
+                ParseDiagnostic::new("asset://dev01/b!ob/model", 14, UriComponent::Name, "a name");
+            assert_eq!(diag.found, "b!ob");
+        }
+
+        #[test]
+        fn found_reports_end_of_uri() {
+            let diag = ParseDiagnostic::new("asset://dev01", 13, UriComponent::Level, "a level");
+            assert_eq!(diag.found, "end of uri");
+        }
+
+        #[test]
+        fn can_render_caret_message() {
+            let diag =
+                ParseDiagnostic::new("asset://dev01/b!ob/model", 14, UriComponent::Name, "a name");
+            assert_eq!(
+                diag.render(),
+                "invalid name: expected a name, found 'b!ob'\n  asset://dev01/b!ob/model\n                ^^^^"
+            );
+        }
+
+        #[test]
+        fn clamps_offset_to_input() {
+            let diag = ParseDiagnostic::new("asset://dev01", 40, UriComponent::Level, "a level");
+            assert_eq!(diag.offset, 13);
+            assert_eq!(diag.found, "end of uri");
+            let diag = ParseDiagnostic::new("asset://dév01", 10, UriComponent::Level, "a level");
+            assert_eq!(diag.offset, 9);
+            assert_eq!(diag.found, "év01");
+            assert!(diag.render().ends_with("\n           ^^^^"));
+        }
+
+        #[test]
+        fn can_render_note() {
+            let diag =
+                ParseDiagnostic::new("asset://dev01/bob1/", 14, UriComponent::Name, "a name")
+                    .with_note("Invalid asset name 'bob1': name must not end with a digit");
+            assert!(diag.to_string().ends_with(
+                "found 'bob1'; Invalid asset name 'bob1': name must not end with a digit"
+            ));
+            assert!(diag
+                .render()
+                .ends_with("^^^^\n  Invalid asset name 'bob1': name must not end with a digit"));
+        }
+
+        #[test]
+        fn can_render_suggestions() {
+            let diag = ParseDiagnostic::new("rendr://", 0, UriComponent::Scheme, "a scheme")
+                .with_suggestions(vec!["render".into()]);
+            assert!(diag.render().ends_with("^^^^^ did you mean 'render'?"));
+            let diag = diag.with_suggestions(vec!["render".into(), "asset".into(), "plate".into()]);
+            assert!(diag
+                .to_string()
+                .ends_with("; did you mean 'render', 'asset' or 'plate'?"));
+        }
+
+        #[test]
+        fn suggestions_are_exposed_on_error() {
+            let diag = ParseDiagnostic::new("rendr://", 0, UriComponent::Scheme, "a scheme")
+                .with_suggestions(vec!["render".into()]);
+            assert_eq!(
+                AmuriError::UriParsingError(diag).suggestions(),
+                &["render".to_string()]
+            );
+            assert!(AmuriError::LevelParsingFailure("x".into())
+                .suggestions()
+                .is_empty());
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Level fallback lookup
+//!
+//! Assets are commonly published at the show level and overridden at the
+//! sequence or shot level. A fallback lookup tries the model's own level,
+//! then each of its ancestors in turn, and settles on the first level at
+//! which the provider reports a publish. Each level tried is recorded, so
+//! that callers may report why a particular level was chosen.
+use crate::assetmodel::AssetModel;
+use crate::level::Level;
+
+/// Reports whether a publish exists for a model
+pub trait ExistenceProvider {
+    /// Test whether a publish exists for the model
+    fn exists(&self, model: &AssetModel<'_>) -> bool;
+}
+
+impl<F> ExistenceProvider for F
+where
+    F: Fn(&AssetModel<'_>) -> bool,
+{
+    fn exists(&self, model: &AssetModel<'_>) -> bool {
+        self(model)
+    }
+}
+
+/// A level tried during a fallback lookup
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Attempt<'a> {
+    /// The level tried
+    pub level: Level<'a>,
+    /// Whether a publish existed at the level
+    pub exists: bool,
+}
+
+/// The outcome of a fallback lookup
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fallback<'a> {
+    found: Option<AssetModel<'a>>,
+    trace: Vec<Attempt<'a>>,
+}
+
+impl<'a> Fallback<'a> {
+    /// Retrieve the model at the first level with a publish, if any
+    pub fn found(&self) -> Option<&AssetModel<'a>> {
+        self.found.as_ref()
+    }
+
+    /// Retrieve the first level with a publish, if any
+    pub fn level(&self) -> Option<&Level<'a>> {
+        self.found.as_ref().map(AssetModel::level)
+    }
+
+    /// Retrieve each level tried, in the order tried
+    pub fn trace(&self) -> &[Attempt<'a>] {
+        &self.trace
+    }
+
+    /// Consume the Fallback, returning the model at the first level with a
+    /// publish, if any
+    pub fn into_found(self) -> Option<AssetModel<'a>> {
+        self.found
+    }
+}
+
+/// Try the model's level and then each of its ancestors, nearest first,
+/// stopping at the first level at which the provider reports a publish
+pub fn find_with_fallback<'a>(
+    model: &AssetModel<'a>,
+    provider: &dyn ExistenceProvider,
+) -> Fallback<'a> {
+    let mut trace = Vec::new();
+    let levels = std::iter::once(model.level().clone()).chain(model.level().ancestors());
+    for level in levels {
+        let candidate = model.with_level(level.clone());
+        let exists = provider.exists(&candidate);
+        trace.push(Attempt { level, exists });
+        if exists {
+            return Fallback {
+                found: Some(candidate),
+                trace,
+            };
+        }
+    }
+    Fallback { found: None, trace }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::uri::parse_uri;
+
+    const URI: &str = "asset://dev01.rd.0001/bob/model/hi/maya_model?version=current#main";
+
+    #[test]
+    fn returns_own_level_when_published() {
+        let model = parse_uri(URI).unwrap();
+        let fallback = find_with_fallback(&model, &|_: &AssetModel<'_>| true);
+        assert_eq!(fallback.found(), Some(&model));
+        assert_eq!(
+            fallback.trace(),
+            &[Attempt {
+                level: Level::shot("dev01", "rd", "0001"),
+                exists: true
+            }]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_nearest_published_ancestor() {
+        let model = parse_uri(URI).unwrap();
+        let published = |m: &AssetModel<'_>| m.level().depth() == 1;
+        let fallback = find_with_fallback(&model, &published);
+        assert_eq!(fallback.level(), Some(&Level::show("dev01")));
+        assert_eq!(
+            fallback.found().map(ToString::to_string),
+            Some("asset://dev01/bob/model/hi/maya_model?version=current#main".to_string())
+        );
+        assert_eq!(
+            fallback
+                .trace()
+                .iter()
+                .map(|attempt| (attempt.level.to_string(), attempt.exists))
+                .collect::<Vec<_>>(),
+            vec![
+                ("dev01.rd.0001".to_string(), false),
+                ("dev01.rd".to_string(), false),
+                ("dev01".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_every_level_tried_when_nothing_is_published() {
+        let model = parse_uri(URI).unwrap();
+        let fallback = find_with_fallback(&model, &|_: &AssetModel<'_>| false);
+        assert_eq!(fallback.found(), None);
+        assert_eq!(fallback.trace().len(), 3);
+        assert!(fallback.trace().iter().all(|attempt| !attempt.exists));
+    }
+}
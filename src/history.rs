@@ -0,0 +1,158 @@
+//! Publish history, used to resolve versions as they were at a point in time
+//!
+//! Which versions are available, which is current, and which carries a tag
+//! all change as new versions are published. A PublishHistory answers those
+//! questions for any moment, so that a uri with an `as_of` timestamp
+//! resolves to the version it would have resolved to at that time.
+use crate::timestamp::Timestamp;
+use crate::version::TagResolver;
+use std::collections::BTreeMap;
+
+/// Provides the state of a container's versions at a point in time
+pub trait PublishHistory {
+    /// The version numbers published at or before as_of
+    fn available_at(&self, as_of: Timestamp) -> Vec<u32>;
+
+    /// The version the current pointer referenced at as_of, if it was set
+    fn current_at(&self, as_of: Timestamp) -> Option<u32>;
+
+    /// The version the tag was bound to at as_of, if it was bound
+    fn tag_at(&self, tag: &str, as_of: Timestamp) -> Option<u32>;
+}
+
+/// Resolves tags against a PublishHistory at a fixed point in time
+pub struct TagsAsOf<'a> {
+    history: &'a dyn PublishHistory,
+    as_of: Timestamp,
+}
+
+impl<'a> TagsAsOf<'a> {
+    /// constructor function
+    pub fn new(history: &'a dyn PublishHistory, as_of: Timestamp) -> Self {
+        Self { history, as_of }
+    }
+}
+
+impl<'a> TagResolver for TagsAsOf<'a> {
+    fn resolve_tag(&self, tag: &str) -> Option<u32> {
+        self.history.tag_at(tag, self.as_of)
+    }
+}
+
+// The value of the last event at or before as_of. Events are kept in the
+// order they were recorded, so later records win ties.
+fn value_at(events: &[(Timestamp, u32)], as_of: Timestamp) -> Option<u32> {
+    events
+        .iter()
+        .enumerate()
+        .filter(|(_, (at, _))| *at <= as_of)
+        .max_by_key(|(idx, (at, _))| (*at, *idx))
+        .map(|(_, (_, version))| *version)
+}
+
+/// An in-memory record of publishes, current pointer changes and tag moves
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PublishLog {
+    published: Vec<(Timestamp, u32)>,
+    current: Vec<(Timestamp, u32)>,
+    tags: BTreeMap<String, Vec<(Timestamp, u32)>>,
+}
+
+impl PublishLog {
+    /// constructor function. The resulting log is empty
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the publish of a version
+    pub fn publish(&mut self, version: u32, at: Timestamp) -> &mut Self {
+        self.published.push((at, version));
+        self
+    }
+
+    /// Record the current pointer being set to a version
+    pub fn set_current(&mut self, version: u32, at: Timestamp) -> &mut Self {
+        self.current.push((at, version));
+        self
+    }
+
+    /// Record a tag being bound to a version
+    pub fn set_tag<I: Into<String>>(&mut self, tag: I, version: u32, at: Timestamp) -> &mut Self {
+        self.tags.entry(tag.into()).or_default().push((at, version));
+        self
+    }
+}
+
+impl PublishHistory for PublishLog {
+    fn available_at(&self, as_of: Timestamp) -> Vec<u32> {
+        let mut available = self
+            .published
+            .iter()
+            .filter(|(at, _)| *at <= as_of)
+            .map(|(_, version)| *version)
+            .collect::<Vec<_>>();
+        available.sort_unstable();
+        available.dedup();
+        available
+    }
+
+    fn current_at(&self, as_of: Timestamp) -> Option<u32> {
+        value_at(&self.current, as_of)
+    }
+
+    fn tag_at(&self, tag: &str, as_of: Timestamp) -> Option<u32> {
+        self.tags
+            .get(tag)
+            .and_then(|events| value_at(events, as_of))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> Timestamp {
+        Timestamp::from_secs(secs)
+    }
+
+    fn log() -> PublishLog {
+        let mut log = PublishLog::new();
+        log.publish(1, at(10))
+            .set_current(1, at(10))
+            .publish(2, at(20))
+            .set_tag("approved", 2, at(25))
+            .publish(3, at(30))
+            .set_current(3, at(30))
+            .set_tag("approved", 3, at(35));
+        log
+    }
+
+    #[test]
+    fn available_versions_grow_over_time() {
+        assert_eq!(log().available_at(at(5)), Vec::<u32>::new());
+        assert_eq!(log().available_at(at(20)), vec![1, 2]);
+        assert_eq!(log().available_at(at(99)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn current_is_last_set_at_time() {
+        assert_eq!(log().current_at(at(5)), None);
+        assert_eq!(log().current_at(at(29)), Some(1));
+        assert_eq!(log().current_at(at(30)), Some(3));
+    }
+
+    #[test]
+    fn tags_move_over_time() {
+        assert_eq!(log().tag_at("approved", at(20)), None);
+        assert_eq!(log().tag_at("approved", at(30)), Some(2));
+        assert_eq!(log().tag_at("approved", at(35)), Some(3));
+        assert_eq!(log().tag_at("techcheck", at(35)), None);
+    }
+
+    #[test]
+    fn later_record_wins_at_same_time() {
+        let mut log = PublishLog::new();
+        log.set_current(2, at(10)).set_current(1, at(10));
+        assert_eq!(log.current_at(at(10)), Some(1));
+    }
+}
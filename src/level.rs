@@ -2,9 +2,30 @@
 //!
 //! The level comes in two flavors: owned and non-owned
 //! It is not clear yet which i will use.
+//!
+//! A level is a list of tiers, starting with the show (eg dev01.rd.0001 has
+//! the tiers dev01, rd and 0001). The names and number of the tiers beneath
+//! the show are described by a `LevelSchema`; by default these are sequence
+//! and shot. Levels of up to three tiers are represented by the Show,
+//! Sequence and Shot variants, and deeper ones by the Tiers variant.
+//!
+//! Levels are ordered hierarchically: tier by tier, with a parent sorting
+//! before its children. (eg dev01 < dev01.rd < dev01.rd.0001 < dev02)
+//!
+//! Levels may be navigated up the hierarchy via `parent` and `ancestors`, and
+//! down it via `child`.
 use crate::errors::AmuriError;
 use crate::parse;
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone)]
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// A location in the show hierarchy, borrowed from a levelspec. Levels of
+/// up to three tiers are a Show, Sequence or Shot, whatever their names in
+/// the show's schema; deeper levels hold all of their tiers.
+#[derive(Debug, Clone)]
 pub enum Level<'a> {
     Show(&'a str),
     Sequence {
@@ -16,6 +37,21 @@ pub enum Level<'a> {
         sequence: &'a str,
         shot: &'a str,
     },
+    /// A level of more than three tiers. Use `from_tiers` to construct one
+    Tiers(DeepTiers<'a>),
+}
+
+/// The tiers of a level of more than three tiers, starting with the show.
+/// These are only constructed by `Level::from_tiers`, which represents
+/// shallower levels by the Show, Sequence and Shot variants
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+pub struct DeepTiers<'a>(Vec<&'a str>);
+
+impl<'a> DeepTiers<'a> {
+    /// Retrieve the tiers, starting with the show
+    pub fn as_slice(&self) -> &[&'a str] {
+        &self.0
+    }
 }
 
 impl<'a> Level<'a> {
@@ -43,27 +79,174 @@ impl<'a> Level<'a> {
         }
     }
 
-    /// parse level from str.
+    /// Construct a level from its tiers, starting with the show. Returns
+    /// None if there are no tiers
+    pub fn from_tiers<I: IntoIterator<Item = &'a str>>(tiers: I) -> Option<Self> {
+        let tiers = tiers.into_iter().collect::<Vec<_>>();
+        match tiers.as_slice() {
+            [] => None,
+            [show] => Some(Self::show(show)),
+            [show, sequence] => Some(Self::seq(show, sequence)),
+            [show, sequence, shot] => Some(Self::shot(show, sequence, shot)),
+            _ => Some(Level::Tiers(DeepTiers(tiers))),
+        }
+    }
+
+    /// parse level from str, using the default level schema.
     // Note: I did not use the trait due to lifetime conflicts with trait
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'a str) -> std::result::Result<Self, AmuriError> {
         let (_, result) = parse::level::parse_level_noslash(input)
             .map_err(|_e| AmuriError::LevelParsingFailure(input.into()))?;
         Ok(result)
     }
 
-    /// Create an OwnedLevel from a level
-    pub fn to_owned(&self) -> OwnedLevel {
+    /// Retrieve the tiers of the level, starting with the show
+    pub fn tiers(&self) -> Vec<&'a str> {
         match self {
-            Self::Show(show) => OwnedLevel::show(*show),
-            Self::Sequence { show, sequence } => OwnedLevel::seq(*show, *sequence),
+            Self::Show(show) => vec![show],
+            Self::Sequence { show, sequence } => vec![show, sequence],
             Self::Shot {
                 show,
                 sequence,
                 shot,
-            } => OwnedLevel::shot(*show, *sequence, *shot),
+            } => vec![show, sequence, shot],
+            Self::Tiers(tiers) => tiers.0.clone(),
+        }
+    }
+
+    /// Retrieve the name of the show
+    pub fn show_name(&self) -> &'a str {
+        match self {
+            Self::Show(show) | Self::Sequence { show, .. } | Self::Shot { show, .. } => show,
+            Self::Tiers(DeepTiers(tiers)) => tiers[0],
         }
     }
+
+    /// Retrieve the name of the lowest tier (eg the shot of a shot level)
+    pub fn leaf_name(&self) -> &'a str {
+        match self {
+            Self::Show(name)
+            | Self::Sequence { sequence: name, .. }
+            | Self::Shot { shot: name, .. } => name,
+            Self::Tiers(DeepTiers(tiers)) => tiers[tiers.len() - 1],
+        }
+    }
+
+    /// The number of tiers in the level: 1 for a show, 2 for a sequence and
+    /// 3 for a shot
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Show(_) => 1,
+            Self::Sequence { .. } => 2,
+            Self::Shot { .. } => 3,
+            Self::Tiers(tiers) => tiers.0.len(),
+        }
+    }
+
+    /// The level one tier up (eg the sequence of a shot). A show has no parent
+    pub fn parent(&self) -> Option<Level<'a>> {
+        match self {
+            Self::Show(_) => None,
+            Self::Sequence { show, .. } => Some(Self::show(show)),
+            Self::Shot { show, sequence, .. } => Some(Self::seq(show, sequence)),
+            Self::Tiers(DeepTiers(tiers)) => {
+                Self::from_tiers(tiers[..tiers.len() - 1].iter().copied())
+            }
+        }
+    }
+
+    /// Iterate over the levels above this one, nearest first (eg the sequence
+    /// and then the show of a shot)
+    pub fn ancestors(&self) -> impl Iterator<Item = Level<'a>> {
+        std::iter::successors(self.parent(), Level::parent)
+    }
+
+    /// The level one tier down with the supplied name (eg a shot within a
+    /// sequence). A shot has no children; schemas with more tiers are
+    /// navigated via `LevelSchema::child`
+    pub fn child(&self, name: &'a str) -> Option<Level<'a>> {
+        match *self {
+            Self::Show(show) => Some(Self::seq(show, name)),
+            Self::Sequence { show, sequence } => Some(Self::shot(show, sequence, name)),
+            Self::Shot { .. } | Self::Tiers(_) => None,
+        }
+    }
+
+    // the level one tier down, whatever the depth of this one. Callers are
+    // responsible for checking the depth against the show's schema
+    pub(crate) fn with_tier(&self, name: &'a str) -> Level<'a> {
+        self.child(name).unwrap_or_else(|| {
+            // only a shot or deeper level has no child, so the result has
+            // more than three tiers
+            let mut tiers = self.tiers();
+            tiers.push(name);
+            Level::Tiers(DeepTiers(tiers))
+        })
+    }
+
+    /// Test whether this level is above the other in the hierarchy (eg a
+    /// show is an ancestor of each of its sequences and shots). A level is
+    /// not its own ancestor
+    pub fn is_ancestor_of(&self, other: &Level<'_>) -> bool {
+        self.depth() < other.depth() && self.contains(other)
+    }
+
+    /// Test whether the other level is this level or lies beneath it
+    pub fn contains(&self, other: &Level<'_>) -> bool {
+        other.tiers().starts_with(&self.tiers())
+    }
+
+    /// Create an OwnedLevel from a level
+    pub fn to_owned(&self) -> OwnedLevel {
+        OwnedLevel::from(self.clone())
+    }
+}
+
+// Levels compare, hash and order by their tiers, so that a Tiers level is
+// never distinguished from the Show, Sequence or Shot with the same tiers
+
+impl<'a> PartialEq for Level<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tiers() == other.tiers()
+    }
+}
+
+impl<'a> Eq for Level<'a> {}
+
+impl<'a> Hash for Level<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tiers().hash(state)
+    }
+}
+
+impl<'a> Ord for Level<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tiers().cmp(&other.tiers())
+    }
+}
+
+impl<'a> PartialOrd for Level<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Level<'a> {
+    type Error = AmuriError;
+
+    fn try_from(input: &'a str) -> Result<Self, Self::Error> {
+        Level::from_str(input)
+    }
+}
+
+impl<'a> fmt::Display for Level<'a> {
+    /// Format the level as a levelspec (eg show.seq.shot)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tiers().join("."))
+    }
 }
+
 impl<'a> From<Level<'a>> for OwnedLevel {
     fn from(input: Level<'a>) -> Self {
         match input {
@@ -74,10 +257,15 @@ impl<'a> From<Level<'a>> for OwnedLevel {
                 sequence,
                 shot,
             } => OwnedLevel::shot(show, sequence, shot),
+            Level::Tiers(DeepTiers(tiers)) => OwnedLevel::Tiers(OwnedDeepTiers(
+                tiers.into_iter().map(String::from).collect(),
+            )),
         }
     }
 }
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone)]
+
+/// A location in the show hierarchy, owning its tiers. See `Level`
+#[derive(Debug, Clone)]
 pub enum OwnedLevel {
     Show(String),
     Sequence {
@@ -89,6 +277,20 @@ pub enum OwnedLevel {
         sequence: String,
         shot: String,
     },
+    /// A level of more than three tiers. Use `from_tiers` to construct one
+    Tiers(OwnedDeepTiers),
+}
+
+/// The tiers of a level of more than three tiers, owning them. See
+/// `DeepTiers`
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+pub struct OwnedDeepTiers(Vec<String>);
+
+impl OwnedDeepTiers {
+    /// Retrieve the tiers, starting with the show
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
 }
 
 impl OwnedLevel {
@@ -122,18 +324,137 @@ impl OwnedLevel {
         }
     }
 
-    /// Generate a levelspec String
-    pub fn to_string(&self) -> String {
+    /// Construct a level from its tiers, starting with the show. Returns
+    /// None if there are no tiers
+    pub fn from_tiers<I, S>(tiers: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let tiers = tiers.into_iter().map(Into::into).collect::<Vec<String>>();
+        Level::from_tiers(tiers.iter().map(String::as_str)).map(OwnedLevel::from)
+    }
+
+    /// Borrow the OwnedLevel as a Level
+    pub fn as_borrowed(&self) -> Level<'_> {
         match self {
-            Self::Show(show) => show.to_string(),
-            Self::Sequence { show, sequence } => format!("{}.{}", show, sequence),
+            Self::Show(show) => Level::show(show),
+            Self::Sequence { show, sequence } => Level::seq(show, sequence),
             Self::Shot {
                 show,
                 sequence,
                 shot,
-            } => format!("{}.{}.{}", show, sequence, shot),
+            } => Level::shot(show, sequence, shot),
+            Self::Tiers(OwnedDeepTiers(tiers)) => {
+                Level::Tiers(DeepTiers(tiers.iter().map(String::as_str).collect()))
+            }
         }
     }
+
+    /// Retrieve the tiers of the level, starting with the show
+    pub fn tiers(&self) -> Vec<&str> {
+        self.as_borrowed().tiers()
+    }
+
+    /// Retrieve the name of the show
+    pub fn show_name(&self) -> &str {
+        self.as_borrowed().show_name()
+    }
+
+    /// Retrieve the name of the lowest tier (eg the shot of a shot level)
+    pub fn leaf_name(&self) -> &str {
+        self.as_borrowed().leaf_name()
+    }
+
+    /// The number of tiers in the level. See `Level::depth`
+    pub fn depth(&self) -> usize {
+        self.as_borrowed().depth()
+    }
+
+    /// The level one tier up. A show has no parent
+    pub fn parent(&self) -> Option<OwnedLevel> {
+        self.as_borrowed().parent().map(OwnedLevel::from)
+    }
+
+    /// Iterate over the levels above this one, nearest first
+    pub fn ancestors(&self) -> impl Iterator<Item = OwnedLevel> + '_ {
+        self.as_borrowed().ancestors().map(OwnedLevel::from)
+    }
+
+    /// The level one tier down with the supplied name. A shot has no children
+    pub fn child<I: Into<String>>(&self, name: I) -> Option<OwnedLevel> {
+        let name = name.into();
+        self.as_borrowed().child(&name).map(OwnedLevel::from)
+    }
+
+    /// Test whether this level is above the other in the hierarchy. A level
+    /// is not its own ancestor
+    pub fn is_ancestor_of(&self, other: &OwnedLevel) -> bool {
+        self.as_borrowed().is_ancestor_of(&other.as_borrowed())
+    }
+
+    /// Test whether the other level is this level or lies beneath it
+    pub fn contains(&self, other: &OwnedLevel) -> bool {
+        self.as_borrowed().contains(&other.as_borrowed())
+    }
+}
+
+impl PartialEq for OwnedLevel {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_borrowed() == other.as_borrowed()
+    }
+}
+
+impl Eq for OwnedLevel {}
+
+impl Hash for OwnedLevel {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_borrowed().hash(state)
+    }
+}
+
+impl Ord for OwnedLevel {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_borrowed().cmp(&other.as_borrowed())
+    }
+}
+
+impl PartialOrd for OwnedLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for OwnedLevel {
+    type Err = AmuriError;
+
+    /// parse level from str, using the default level schema
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Level::from_str(input).map(OwnedLevel::from)
+    }
+}
+
+impl TryFrom<&str> for OwnedLevel {
+    type Error = AmuriError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::from_str(input)
+    }
+}
+
+impl TryFrom<String> for OwnedLevel {
+    type Error = AmuriError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::from_str(&input)
+    }
+}
+
+impl fmt::Display for OwnedLevel {
+    /// Format the level as a levelspec (eg show.seq.shot)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_borrowed().fmt(f)
+    }
 }
 
 #[cfg(test)]
@@ -185,4 +506,156 @@ mod tests {
             assert_eq!(owned, OwnedLevel::show("dev02"));
         }
     }
+    mod owned_level {
+        use super::*;
+        #[test]
+        fn can_parse_owned_level_from_str() {
+            assert_eq!(
+                "dev02.rd".parse::<OwnedLevel>(),
+                Ok(OwnedLevel::seq("dev02", "rd"))
+            );
+            assert_eq!(
+                OwnedLevel::try_from(String::from("dev02.rd.9999")),
+                Ok(OwnedLevel::shot("dev02", "rd", "9999"))
+            );
+        }
+        #[test]
+        fn owned_level_from_str_will_fail_with_invalid_input() {
+            assert_eq!(
+                OwnedLevel::try_from("_dev02"),
+                Err(AmuriError::LevelParsingFailure("_dev02".into()))
+            );
+        }
+        #[test]
+        fn can_borrow_owned_level() {
+            let owned = OwnedLevel::shot("dev02", "rd", "9999");
+            assert_eq!(owned.as_borrowed(), Level::shot("dev02", "rd", "9999"));
+        }
+    }
+    mod ordering {
+        use super::*;
+        #[test]
+        fn levels_sort_hierarchically() {
+            let mut levels = vec![
+                Level::show("dev02"),
+                Level::shot("dev01", "rd", "0001"),
+                Level::seq("dev01", "ab"),
+                Level::show("dev01"),
+                Level::seq("dev01", "rd"),
+            ];
+            levels.sort();
+            assert_eq!(
+                levels,
+                vec![
+                    Level::show("dev01"),
+                    Level::seq("dev01", "ab"),
+                    Level::seq("dev01", "rd"),
+                    Level::shot("dev01", "rd", "0001"),
+                    Level::show("dev02"),
+                ]
+            );
+        }
+        #[test]
+        fn owned_levels_sort_like_levels() {
+            assert!(OwnedLevel::show("dev01") < OwnedLevel::seq("dev01", "rd"));
+            assert!(OwnedLevel::shot("dev01", "rd", "0001") < OwnedLevel::show("dev02"));
+        }
+    }
+    mod navigation {
+        use super::*;
+        #[test]
+        fn can_retrieve_depth() {
+            assert_eq!(Level::show("dev01").depth(), 1);
+            assert_eq!(Level::seq("dev01", "rd").depth(), 2);
+            assert_eq!(OwnedLevel::shot("dev01", "rd", "0001").depth(), 3);
+        }
+        #[test]
+        fn can_walk_up_the_hierarchy() {
+            let shot = Level::shot("dev01", "rd", "0001");
+            assert_eq!(shot.parent(), Some(Level::seq("dev01", "rd")));
+            assert_eq!(Level::show("dev01").parent(), None);
+            assert_eq!(
+                shot.ancestors().collect::<Vec<_>>(),
+                vec![Level::seq("dev01", "rd"), Level::show("dev01")]
+            );
+            assert_eq!(
+                shot.to_owned().ancestors().collect::<Vec<_>>(),
+                vec![OwnedLevel::seq("dev01", "rd"), OwnedLevel::show("dev01")]
+            );
+        }
+        #[test]
+        fn can_walk_down_the_hierarchy() {
+            let show = Level::show("dev01");
+            assert_eq!(show.child("rd"), Some(Level::seq("dev01", "rd")));
+            assert_eq!(
+                show.child("rd").and_then(|seq| seq.child("0001")),
+                Some(Level::shot("dev01", "rd", "0001"))
+            );
+            assert_eq!(Level::shot("dev01", "rd", "0001").child("x"), None);
+            assert_eq!(
+                OwnedLevel::seq("dev01", "rd").child(String::from("0001")),
+                Some(OwnedLevel::shot("dev01", "rd", "0001"))
+            );
+        }
+        #[test]
+        fn can_construct_from_tiers() {
+            let level = Level::from_tiers(vec!["dev01", "ep01", "rd", "0001"]).unwrap();
+            assert_eq!(level.depth(), 4);
+            assert_eq!(level.show_name(), "dev01");
+            assert_eq!(level.leaf_name(), "0001");
+            assert_eq!(level.to_string(), "dev01.ep01.rd.0001");
+            assert_eq!(level.parent(), Some(Level::shot("dev01", "ep01", "rd")));
+            assert_eq!(level.child("x"), None);
+            match &level {
+                Level::Tiers(tiers) => {
+                    assert_eq!(tiers.as_slice(), ["dev01", "ep01", "rd", "0001"])
+                }
+                _ => panic!("expected a Tiers level"),
+            }
+            assert!(matches!(
+                Level::from_tiers(vec!["dev01", "rd"]),
+                Some(Level::Sequence { .. })
+            ));
+            assert!(matches!(
+                OwnedLevel::from(level.clone()),
+                OwnedLevel::Tiers(ref tiers) if tiers.as_slice().len() == 4
+            ));
+            assert_eq!(
+                level.with_tier("x").tiers(),
+                vec!["dev01", "ep01", "rd", "0001", "x"]
+            );
+            assert_eq!(
+                Level::from_tiers(vec!["dev01", "rd", "0001"]),
+                Some(Level::shot("dev01", "rd", "0001"))
+            );
+            assert_eq!(Level::from_tiers(Vec::new()), None);
+            assert_eq!(
+                OwnedLevel::from_tiers(vec!["dev01", "ep01"]),
+                Some(Level::seq("dev01", "ep01").to_owned())
+            );
+        }
+        #[test]
+        fn can_test_containment() {
+            let show = Level::show("dev01");
+            let shot = Level::shot("dev01", "rd", "0001");
+            assert!(show.is_ancestor_of(&shot));
+            assert!(!shot.is_ancestor_of(&show));
+            assert!(!show.is_ancestor_of(&show));
+            assert!(show.contains(&show));
+            assert!(!Level::show("dev02").contains(&shot));
+            assert!(!Level::seq("dev01", "ab").contains(&shot));
+            assert!(OwnedLevel::show("dev01").contains(&shot.to_owned()));
+        }
+    }
+    mod display {
+        use super::*;
+        #[test]
+        fn can_display_level() {
+            for spec in &["dev02", "dev02.rd", "dev02.rd.9999"] {
+                let level = Level::from_str(spec).unwrap();
+                assert_eq!(&level.to_string(), spec);
+                assert_eq!(&level.to_owned().to_string(), spec);
+            }
+        }
+    }
 }
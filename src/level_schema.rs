@@ -0,0 +1,243 @@
+//! Level schemas
+//!
+//! A LevelSchema names the tiers of a level, starting with the show (eg
+//! show, episode, sequence, shot). A level may stop at any tier, but may not
+//! go deeper than its schema. LevelSchemas assigns a schema to each show,
+//! falling back on a default of show, sequence and shot.
+use crate::errors::AmuriError;
+use crate::level::Level;
+use crate::parse::{level::parse_level_with, resource::parse_resource};
+use nom::combinator::all_consuming;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The names and order of the tiers of a level
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct LevelSchema {
+    tiers: Vec<String>,
+}
+
+impl Default for LevelSchema {
+    /// The default schema: show, sequence and shot
+    fn default() -> Self {
+        Self {
+            tiers: vec!["show".into(), "sequence".into(), "shot".into()],
+        }
+    }
+}
+
+impl LevelSchema {
+    /// constructor function. The first tier is the show. Each tier name must
+    /// be unique, start with a letter, and contain only letters, digits and
+    /// underscores
+    pub fn new<I, S>(tiers: I) -> Result<Self, AmuriError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let tiers = tiers.into_iter().map(Into::into).collect::<Vec<String>>();
+        if tiers.is_empty() {
+            return Err(AmuriError::InvalidLevelSchema(
+                "a schema requires at least one tier".into(),
+            ));
+        }
+        let mut seen = BTreeSet::new();
+        for tier in &tiers {
+            if all_consuming(parse_resource)(tier.as_str()).is_err() {
+                return Err(AmuriError::InvalidLevelSchema(format!(
+                    "invalid tier name '{}'",
+                    tier
+                )));
+            }
+            if !seen.insert(tier.as_str()) {
+                return Err(AmuriError::InvalidLevelSchema(format!(
+                    "duplicate tier name '{}'",
+                    tier
+                )));
+            }
+        }
+        Ok(Self { tiers })
+    }
+
+    /// Retrieve the names of the tiers, starting with the show
+    pub fn tiers(&self) -> &[String] {
+        &self.tiers
+    }
+
+    /// The maximum number of tiers in a level
+    pub fn depth(&self) -> usize {
+        self.tiers.len()
+    }
+
+    /// Test whether the level has no more tiers than the schema
+    pub fn permits(&self, level: &Level<'_>) -> bool {
+        level.depth() <= self.depth()
+    }
+
+    /// Retrieve the name of the lowest tier of the level (eg episode for
+    /// dev01.ep01 in an episodic schema)
+    pub fn tier_name(&self, level: &Level<'_>) -> Option<&str> {
+        self.tiers.get(level.depth() - 1).map(String::as_str)
+    }
+
+    /// Retrieve the value of the named tier of the level, if the level
+    /// reaches that tier (eg the episode of dev01.ep01.rd.0001)
+    pub fn tier<'a>(&self, level: &Level<'a>, name: &str) -> Option<&'a str> {
+        self.tiers
+            .iter()
+            .position(|tier| tier == name)
+            .and_then(|idx| level.tiers().get(idx).copied())
+    }
+
+    /// The level one tier down with the supplied name, if the schema has a
+    /// tier below the level
+    pub fn child<'a>(&self, level: &Level<'a>, name: &'a str) -> Option<Level<'a>> {
+        if level.depth() < self.depth() {
+            Some(level.with_tier(name))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for LevelSchema {
+    /// Format the schema as a levelspec of tier names (eg show.sequence.shot)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tiers.join("."))
+    }
+}
+
+/// The level schema of each show
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct LevelSchemas {
+    default: LevelSchema,
+    shows: BTreeMap<String, LevelSchema>,
+}
+
+impl LevelSchemas {
+    /// constructor function. Every show uses the default schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the schema used by shows without a schema of their own
+    pub fn with_default(mut self, schema: LevelSchema) -> Self {
+        self.default = schema;
+        self
+    }
+
+    /// Set the schema for a show
+    pub fn with_show<I: Into<String>>(mut self, show: I, schema: LevelSchema) -> Self {
+        self.shows.insert(show.into(), schema);
+        self
+    }
+
+    /// Retrieve the schema used by shows without a schema of their own
+    pub fn default_schema(&self) -> &LevelSchema {
+        &self.default
+    }
+
+    /// Iterate over the shows with a schema of their own, along with their
+    /// schemas, in alphabetical order of show
+    pub fn shows(&self) -> impl Iterator<Item = (&str, &LevelSchema)> {
+        self.shows
+            .iter()
+            .map(|(show, schema)| (show.as_str(), schema))
+    }
+
+    /// Retrieve the schema for a show
+    pub fn for_show(&self, show: &str) -> &LevelSchema {
+        self.shows.get(show).unwrap_or(&self.default)
+    }
+
+    /// parse a levelspec, limited to the depth of the schema for its show
+    pub fn parse_level<'a>(&self, input: &'a str) -> Result<Level<'a>, AmuriError> {
+        all_consuming(parse_level_with(self))(input)
+            .map(|(_, level)| level)
+            .map_err(|_| AmuriError::LevelParsingFailure(input.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episodic() -> LevelSchema {
+        LevelSchema::new(vec!["show", "episode", "sequence", "shot"]).unwrap()
+    }
+
+    mod level_schema {
+        use super::*;
+
+        #[test]
+        fn default_schema_is_show_sequence_shot() {
+            assert_eq!(LevelSchema::default().to_string(), "show.sequence.shot");
+            assert_eq!(LevelSchema::default().depth(), 3);
+        }
+
+        #[test]
+        fn rejects_invalid_schema() {
+            assert_eq!(
+                LevelSchema::new(Vec::<String>::new()),
+                Err(AmuriError::InvalidLevelSchema(
+                    "a schema requires at least one tier".into()
+                ))
+            );
+            assert_eq!(
+                LevelSchema::new(vec!["show", "shot", "shot"]),
+                Err(AmuriError::InvalidLevelSchema(
+                    "duplicate tier name 'shot'".into()
+                ))
+            );
+            assert!(LevelSchema::new(vec!["show", "0shot"]).is_err());
+        }
+
+        #[test]
+        fn can_retrieve_tiers_by_name() {
+            let level = Level::from_tiers(vec!["dev01", "ep01", "rd", "0001"]).unwrap();
+            assert_eq!(episodic().tier(&level, "episode"), Some("ep01"));
+            assert_eq!(episodic().tier(&level, "shot"), Some("0001"));
+            assert_eq!(episodic().tier(&level, "asset"), None);
+            assert_eq!(episodic().tier_name(&level), Some("shot"));
+            assert_eq!(episodic().tier(&Level::show("dev01"), "episode"), None);
+        }
+
+        #[test]
+        fn child_is_limited_by_depth() {
+            let schema = LevelSchema::new(vec!["show", "shot"]).unwrap();
+            let shot = schema.child(&Level::show("dev01"), "0001").unwrap();
+            assert_eq!(shot.to_string(), "dev01.0001");
+            assert_eq!(schema.child(&shot, "x"), None);
+            assert!(!schema.permits(&Level::shot("dev01", "rd", "0001")));
+        }
+    }
+
+    mod level_schemas {
+        use super::*;
+
+        #[test]
+        fn shows_fall_back_on_default() {
+            let schemas = LevelSchemas::new().with_show("dev02", episodic());
+            assert_eq!(schemas.for_show("dev02"), &episodic());
+            assert_eq!(schemas.for_show("dev01"), &LevelSchema::default());
+            assert_eq!(schemas.default_schema(), &LevelSchema::default());
+            assert_eq!(
+                schemas.shows().collect::<Vec<_>>(),
+                vec![("dev02", &episodic())]
+            );
+        }
+
+        #[test]
+        fn parse_follows_show_schema() {
+            let schemas = LevelSchemas::new().with_show("dev02", episodic());
+            assert_eq!(
+                schemas.parse_level("dev02.ep01.rd.0001").map(|l| l.depth()),
+                Ok(4)
+            );
+            assert_eq!(
+                schemas.parse_level("dev01.ep01.rd.0001"),
+                Err(AmuriError::LevelParsingFailure("dev01.ep01.rd.0001".into()))
+            );
+        }
+    }
+}
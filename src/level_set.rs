@@ -0,0 +1,317 @@
+//! Sets of levels
+//!
+//! A LevelSet addresses many levels at once by replacing the lowest tier of
+//! a levelspec with a range or list of names:
+//! - a range of numbered shots, which includes its end (eg dev01.rd.0001-0100)
+//! - a range with a step (eg dev01.rd.0010-0100x10)
+//! - a list of names (eg dev01.rd.{0010,0020,0030})
+//!
+//! Expanding a range preserves the zero padding of its start. A set may
+//! expand to at most MAX_LEVEL_SET_SIZE levels.
+use crate::errors::AmuriError;
+use crate::level::{Level, OwnedLevel};
+use crate::level_schema::LevelSchemas;
+use crate::parse::level::parse_level_set_with;
+use nom::combinator::all_consuming;
+use std::fmt;
+
+/// The largest number of levels a LevelSet may expand to
+pub const MAX_LEVEL_SET_SIZE: u64 = 10_000;
+
+/// A range of numbered names, such as shots
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ShotRange {
+    start: u32,
+    end: u32,
+    step: u32,
+    width: usize,
+}
+
+impl ShotRange {
+    /// constructor function. The range includes its end. Returns None if
+    /// the start exceeds the end or the step is zero
+    pub fn new(start: u32, end: u32, step: u32, width: usize) -> Option<Self> {
+        if start > end || step == 0 {
+            None
+        } else {
+            Some(Self {
+                start,
+                end,
+                step,
+                width,
+            })
+        }
+    }
+
+    /// Retrieve the first number in the range
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    /// Retrieve the last number the range may reach
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+
+    /// Retrieve the step between numbers in the range
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// Retrieve the width to which each number is zero padded
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of names in the range
+    pub fn size(&self) -> u64 {
+        u64::from((self.end - self.start) / self.step) + 1
+    }
+
+    /// Iterate over the names in the range, zero padded to its width
+    pub fn names(&self) -> impl Iterator<Item = String> {
+        let width = self.width;
+        (self.start..=self.end)
+            .step_by(self.step as usize)
+            .map(move |n| format!("{:0width$}", n, width = width))
+    }
+}
+
+impl fmt::Display for ShotRange {
+    /// Format the range (eg 0001-0100 or 0010-0100x10)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:0width$}-{:0width$}",
+            self.start,
+            self.end,
+            width = self.width
+        )?;
+        if self.step != 1 {
+            write!(f, "x{}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
+/// The names of the lowest tier of a LevelSet with many members
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Leaves<'a> {
+    /// A range of numbered names
+    Range(ShotRange),
+    /// A list of names
+    List(Vec<&'a str>),
+}
+
+impl<'a> Leaves<'a> {
+    /// The number of names
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Range(range) => range.size(),
+            Self::List(names) => names.len() as u64,
+        }
+    }
+
+    /// Iterate over the names
+    pub fn names(&self) -> Box<dyn Iterator<Item = String> + '_> {
+        match self {
+            Self::Range(range) => Box::new(range.names()),
+            Self::List(names) => Box::new(names.iter().map(|name| name.to_string())),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Leaves<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Range(range) => write!(f, "{}", range),
+            Self::List(names) => write!(f, "{{{}}}", names.join(",")),
+        }
+    }
+}
+
+/// One or more levels, addressed by a levelspec whose lowest tier may be a
+/// range or list of names
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum LevelSet<'a> {
+    /// A single level
+    One(Level<'a>),
+    /// The levels beneath parent named by leaves
+    Many {
+        parent: Level<'a>,
+        leaves: Leaves<'a>,
+    },
+}
+
+impl<'a> LevelSet<'a> {
+    /// parse a LevelSet from str, using the default level schema
+    // Note: I did not use the trait due to lifetime conflicts with trait
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'a str) -> Result<Self, AmuriError> {
+        Self::from_str_with_schemas(input, &LevelSchemas::default())
+    }
+
+    /// parse a LevelSet from str, limited to the depth of the schema for its
+    /// show
+    pub fn from_str_with_schemas(
+        input: &'a str,
+        schemas: &LevelSchemas,
+    ) -> Result<Self, AmuriError> {
+        all_consuming(parse_level_set_with(schemas))(input)
+            .map(|(_, set)| set)
+            .map_err(|_| AmuriError::LevelParsingFailure(input.into()))
+    }
+
+    /// Retrieve the name of the show shared by each member of the set
+    pub fn show_name(&self) -> &'a str {
+        match self {
+            Self::One(level) => level.show_name(),
+            Self::Many { parent, .. } => parent.show_name(),
+        }
+    }
+
+    /// The number of levels in the set
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::One(_) => 1,
+            Self::Many { leaves, .. } => leaves.size(),
+        }
+    }
+
+    /// Expand the set into its individual levels, in order. Returns an
+    /// error if the set holds more than MAX_LEVEL_SET_SIZE levels
+    pub fn expand(&self) -> Result<Vec<OwnedLevel>, AmuriError> {
+        if self.size() > MAX_LEVEL_SET_SIZE {
+            return Err(AmuriError::LevelSetTooLarge {
+                set: self.to_string(),
+                size: self.size(),
+                limit: MAX_LEVEL_SET_SIZE,
+            });
+        }
+        Ok(match self {
+            Self::One(level) => vec![level.to_owned()],
+            Self::Many { parent, leaves } => leaves
+                .names()
+                .map(|name| parent.with_tier(&name).to_owned())
+                .collect(),
+        })
+    }
+}
+
+impl<'a> From<Level<'a>> for LevelSet<'a> {
+    fn from(level: Level<'a>) -> Self {
+        Self::One(level)
+    }
+}
+
+impl<'a> fmt::Display for LevelSet<'a> {
+    /// Format the set as a levelspec (eg dev01.rd.0001-0100)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::One(level) => write!(f, "{}", level),
+            Self::Many { parent, leaves } => write!(f, "{}.{}", parent, leaves),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(set: &LevelSet<'_>) -> Vec<String> {
+        set.expand()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn can_expand_range_preserving_padding() {
+        let set = LevelSet::from_str("dev01.rd.0001-0003").unwrap();
+        assert_eq!(
+            names(&set),
+            vec!["dev01.rd.0001", "dev01.rd.0002", "dev01.rd.0003"]
+        );
+        assert_eq!(
+            set.expand().unwrap()[0],
+            OwnedLevel::shot("dev01", "rd", "0001")
+        );
+        assert_eq!(set.size(), 3);
+    }
+
+    #[test]
+    fn can_expand_range_with_step() {
+        let set = LevelSet::from_str("dev01.rd.0010-0045x10").unwrap();
+        assert_eq!(
+            names(&set),
+            vec![
+                "dev01.rd.0010",
+                "dev01.rd.0020",
+                "dev01.rd.0030",
+                "dev01.rd.0040"
+            ]
+        );
+    }
+
+    #[test]
+    fn can_expand_list() {
+        let set = LevelSet::from_str("dev01.rd.{0010,0020,0030}").unwrap();
+        assert_eq!(
+            names(&set),
+            vec!["dev01.rd.0010", "dev01.rd.0020", "dev01.rd.0030"]
+        );
+    }
+
+    #[test]
+    fn single_level_expands_to_itself() {
+        let set = LevelSet::from_str("dev01.rd").unwrap();
+        assert_eq!(set, LevelSet::One(Level::seq("dev01", "rd")));
+        assert_eq!(names(&set), vec!["dev01.rd"]);
+        assert_eq!(names(&LevelSet::from_str("dev01").unwrap()), vec!["dev01"]);
+    }
+
+    #[test]
+    fn rejects_oversized_sets() {
+        let set = LevelSet::from_str("dev01.rd.0-4000000000").unwrap();
+        assert_eq!(set.size(), 4_000_000_001);
+        assert_eq!(
+            set.expand(),
+            Err(AmuriError::LevelSetTooLarge {
+                set: "dev01.rd.0-4000000000".into(),
+                size: 4_000_000_001,
+                limit: MAX_LEVEL_SET_SIZE,
+            })
+        );
+        let set = LevelSet::from_str("dev01.rd.1-10000").unwrap();
+        assert_eq!(set.expand().map(|levels| levels.len()), Ok(10_000));
+        assert_eq!(
+            LevelSet::from_str("dev01.rd.0-4000000000x1000000").map(|set| set.size()),
+            Ok(4001)
+        );
+    }
+
+    #[test]
+    fn can_display_level_set() {
+        for spec in &[
+            "dev01.rd.0001-0100",
+            "dev01.rd.0010-0100x10",
+            "dev01.rd.{0010,0020}",
+            "dev01.rd",
+        ] {
+            assert_eq!(&LevelSet::from_str(spec).unwrap().to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_sets() {
+        assert_eq!(
+            LevelSet::from_str("dev01.rd.0100-0001"),
+            Err(AmuriError::LevelParsingFailure("dev01.rd.0100-0001".into()))
+        );
+        assert!(LevelSet::from_str("dev01.rd.0001-0100x0").is_err());
+        assert!(LevelSet::from_str("dev01.{rd,ab}.0001").is_err());
+        assert!(LevelSet::from_str("dev01.rd.0001.0001-0002").is_err());
+        assert!(ShotRange::new(1, 2, 0, 4).is_none());
+    }
+}
@@ -5,18 +5,46 @@
 //! asset://dev01/thanos/model/hi/maya_model?version=current#main
 //! instance:/dev01.rd.9999/thanos1/anim/hi/alembic_cache?version=current#main
 //!
+//! The path between the level and the query follows the route layout of the
+//! scheme (see `route`). Instances may name their source asset after the name,
+//! renders add a layer and pass, and plates have no department or subcontext.
+//!
 //! Uri-ish because we leave out the authority (ie no server:port )
-//! valid schemes:
+//! built-in schemes:
 //! - asset
 //! - instance
 //! - render
 //! - plate
 //!
+//! further schemes may be registered with a SchemeRegistry. Unless parsing is
+//! strict, schemes may be written by alias (eg inst, rnd) or with a single
+//! slash (eg instance:/), and are canonicalized
+//!
 //! valid name, dept, subcontext, type:
 //! char (a-z|0-9_)+
+//!
+//! valid resource path segment:
+//! char (a-z|0-9_-.)+ starting with a letter or digit
+#![cfg_attr(
+    test,
+    allow(clippy::bool_assert_comparison, clippy::needless_range_loop)
+)]
 pub mod assetmodel;
+pub mod builder;
+pub mod config;
+pub mod describe;
 pub mod errors;
+pub mod fallback;
+pub mod history;
 pub mod level;
+pub mod level_schema;
+pub mod level_set;
 pub mod parse;
+pub mod pattern;
+pub mod route;
 pub mod scheme;
+pub mod scheme_registry;
+pub mod timestamp;
 pub mod version;
+pub mod version_req;
+pub mod vocabulary;
@@ -2,12 +2,69 @@
 //!
 //! Given the assumption that we are here:
 //! scheme:level/name
+//!
+//! Each scheme places its own restriction on the name, described by a NameRule:
+//! - asset names may not end in a digit
+//! - instance names must end in a digit
+//! - render and plate names are held only to the general name rules, and so
+//!   may end in a letter or a digit
 
 use crate::parse::resource::parse_resource;
 use crate::parse::utils::*;
 use nom::bytes::complete::tag;
 use nom::sequence::terminated;
-use nom::{character, combinator::verify, IResult};
+use nom::{
+    character,
+    combinator::{all_consuming, verify},
+    IResult,
+};
+
+/// The rule that a container name must follow in addition to the general
+/// name rules (start with a letter, no trailing underscore)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NameRule {
+    /// The name may not end in a digit
+    NoTrailingDigit,
+    /// The name must end in a digit
+    TrailingDigit,
+    /// The name may end in either a letter or a digit
+    Any,
+}
+
+impl NameRule {
+    /// A human readable description of the rule
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::NoTrailingDigit => "name must not end with a digit",
+            Self::TrailingDigit => "name must end with a digit",
+            Self::Any => "name must start with a letter and not end with an underscore",
+        }
+    }
+
+    /// Describe the name which satisfies the rule, as reported when parsing
+    /// fails
+    pub fn expected(&self) -> &'static str {
+        match self {
+            Self::NoTrailingDigit => "a name starting with a letter and not ending with a digit",
+            Self::TrailingDigit => "a name starting with a letter and ending with a digit",
+            Self::Any => "a name starting with a letter",
+        }
+    }
+
+    /// parse a name, not ending in a slash, which satisfies the rule
+    pub fn parse<'a>(&self, input: &'a str) -> IResult<&'a str, &'a str> {
+        match self {
+            Self::NoTrailingDigit => parse_asset_name_(input),
+            Self::TrailingDigit => parse_instance_name_(input),
+            Self::Any => parse_name_noslash(input),
+        }
+    }
+
+    /// Test whether the entirety of the supplied name satisfies the rule
+    pub fn matches(&self, name: &str) -> bool {
+        all_consuming(|i| self.parse(i))(name).is_ok()
+    }
+}
 
 fn parse_asset_name_(input: &str) -> IResult<&str, &str> {
     let (i, r) = verify(parse_resource, |s: &str| {
@@ -22,11 +79,9 @@ pub fn parse_asset_name(input: &str) -> IResult<&str, &str> {
 }
 
 fn parse_instance_name_(input: &str) -> IResult<&str, &str> {
-    let (i, r1) = character::complete::alpha1(input)?;
-    let (i, r2) = verify(valid_body0_parser, |s: &str| {
-        character::is_digit(s.chars().last().unwrap_or('1') as u8)
-    })(i)?;
-    Ok((i, &input[0..r1.len() + r2.len()]))
+    verify(parse_name_noslash, |s: &str| {
+        character::is_digit(s.chars().last().unwrap_or('a') as u8)
+    })(input)
 }
 
 /// given a valid show string, return a show and whatever is left over
@@ -34,6 +89,16 @@ pub fn parse_instance_name(input: &str) -> IResult<&str, &str> {
     terminated(parse_instance_name_, tag("/"))(input)
 }
 
+/// given a valid render name, return it and whatever is left over
+pub fn parse_render_name(input: &str) -> IResult<&str, &str> {
+    terminated(|i| NameRule::Any.parse(i), tag("/"))(input)
+}
+
+/// given a valid plate name, return it and whatever is left over
+pub fn parse_plate_name(input: &str) -> IResult<&str, &str> {
+    terminated(|i| NameRule::Any.parse(i), tag("/"))(input)
+}
+
 /// parse name not ending in a slash
 pub(crate) fn parse_name_noslash(input: &str) -> IResult<&str, &str> {
     let (i, r1) = character::complete::alpha1(input)?;
@@ -124,6 +189,13 @@ mod tests {
             );
         }
         #[test]
+        fn cannot_consist_solely_of_letters() {
+            assert_eq!(
+                parse_instance_name("fred/"),
+                Err(Error(("fred/", ErrorKind::Verify)))
+            );
+        }
+        #[test]
         fn cannot_end_with_an_underscore() {
             assert_eq!(
                 parse_instance_name("fred_/"),
@@ -145,6 +217,36 @@ mod tests {
             );
         }
     }
+    mod parse_render_name {
+        use super::*;
+
+        #[test]
+        fn can_end_with_letter_or_number() {
+            assert_eq!(parse_render_name("beauty/"), Ok(("", "beauty")));
+            assert_eq!(parse_render_name("beauty2/"), Ok(("", "beauty2")));
+        }
+    }
+    mod parse_plate_name {
+        use super::*;
+
+        #[test]
+        fn can_end_with_letter_or_number() {
+            assert_eq!(parse_plate_name("bg01/"), Ok(("", "bg01")));
+            assert_eq!(parse_plate_name("bg/"), Ok(("", "bg")));
+        }
+    }
+    mod name_rule {
+        use super::*;
+
+        #[test]
+        fn can_match_entire_name() {
+            assert!(NameRule::NoTrailingDigit.matches("bob"));
+            assert!(!NameRule::NoTrailingDigit.matches("bob1"));
+            assert!(NameRule::TrailingDigit.matches("bob1"));
+            assert!(!NameRule::TrailingDigit.matches("bob"));
+            assert!(!NameRule::Any.matches("bob!"));
+        }
+    }
     mod parse_name {
         use super::*;
         #[test]
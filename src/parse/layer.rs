@@ -0,0 +1,7 @@
+use super::container_name::parse_name;
+use nom::IResult;
+
+/// Retrieve the render layer name given an input str
+pub fn parse_layer(input: &str) -> IResult<&str, &str> {
+    parse_name(input)
+}
@@ -1,23 +1,33 @@
 //! Simplified levelspec parser
 //!
-//! level may be
+//! Under the default schema, level may be
 //! show
 //! show.seq
 //! show.seq.shot
-//! show
+//!
+//! Other schemas permit other depths (eg show.episode.seq.shot). The show
+//! must start with a letter; the tiers beneath it may start with a digit.
+//!
+//! A level set may replace the lowest tier with a range (eg 0001-0100 or
+//! 0010-0100x10) or a list (eg {0010,0020,0030}).
 
 use crate::level::Level;
+use crate::level_schema::LevelSchemas;
+use crate::level_set::{Leaves, LevelSet, ShotRange};
 use crate::parse::resource::parse_resource;
 use crate::parse::utils::*;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::sequence::{preceded, separated_pair, terminated};
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_opt, opt};
+use nom::multi::{many_m_n, separated_nonempty_list};
+use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
 use nom::{character, error::ErrorKind, IResult};
 
 pub fn parse_level_item(input: &str) -> IResult<&str, &str> {
     let (i, r1) = character::complete::alphanumeric1(input)?;
     let (i, r2) = valid_body0_parser(i)?;
-    if r2.len() > 0 {
+    if !r2.is_empty() {
         // This will be a bit slower, but will be utf8 compliant. We want a
         // slice that is guaranteed to consist of the last character of the str.
         // we need to figure out how big that last character is in utf8, and use
@@ -35,35 +45,129 @@ pub fn parse_level_item(input: &str) -> IResult<&str, &str> {
 }
 
 /// given a valid show string, return a show and whatever is left over
-pub fn parse_show(input: &str) -> IResult<&str, Level> {
+pub fn parse_show(input: &str) -> IResult<&str, Level<'_>> {
     let (i, r) = parse_resource(input)?;
-    Ok((i, Level::Show(r)))
+    Ok((i, Level::show(r)))
 }
 
-/// given a valid seq str, return a Level::Seq and whatever is left over
-pub fn parse_seq(input: &str) -> IResult<&str, Level> {
-    let (i, (sh, seq)) = separated_pair(parse_resource, tag("."), parse_resource)(input)?;
+/// given a valid seq str, return a sequence Level and whatever is left over.
+/// As with `parse_level_with`, the sequence may start with a digit
+pub fn parse_seq(input: &str) -> IResult<&str, Level<'_>> {
+    let (i, (sh, seq)) = separated_pair(parse_resource, tag("."), parse_level_item)(input)?;
     Ok((i, Level::seq(sh, seq)))
 }
 /// For those who prefer the full name
-pub fn parse_sequence(input: &str) -> IResult<&str, Level> {
+pub fn parse_sequence(input: &str) -> IResult<&str, Level<'_>> {
     parse_seq(input)
 }
-/// parse a shot returning a Level::Shot instance
-pub fn parse_shot(input: &str) -> IResult<&str, Level> {
+/// parse a shot returning a shot Level
+pub fn parse_shot(input: &str) -> IResult<&str, Level<'_>> {
     let (i, (sh, seq)) = separated_pair(parse_resource, tag("."), parse_level_item)(input)?;
     let (i, shot) = preceded(tag("."), parse_level_item)(i)?;
     Ok((i, Level::shot(sh, seq, shot)))
 }
 
+/// Return a parser for a levelspec string whose depth is limited by the
+/// level schema of its show. Parsing stops at the deepest tier of the schema
+pub fn parse_level_with<'a, 's>(
+    schemas: &'s LevelSchemas,
+) -> impl Fn(&'a str) -> IResult<&'a str, Level<'a>> + 's {
+    move |input: &'a str| {
+        let (i, show) = parse_resource(input)?;
+        let depth = schemas.for_show(show).depth();
+        let (i, tiers) = many_m_n(0, depth - 1, preceded(tag("."), parse_level_item))(i)?;
+        let level = tiers
+            .into_iter()
+            .fold(Level::show(show), |level, tier| level.with_tier(tier));
+        Ok((i, level))
+    }
+}
+
+/// parse a range of numbered names with an optional step (eg 0001-0100 or
+/// 0010-0100x10), preserving the zero padding of the start
+pub fn parse_shot_range(input: &str) -> IResult<&str, ShotRange> {
+    map_opt(
+        tuple((
+            digit1,
+            preceded(tag("-"), digit1),
+            opt(preceded(tag("x"), digit1)),
+        )),
+        |(start, end, step): (&str, &str, Option<&str>)| {
+            let step = match step {
+                Some(step) => step.parse().ok()?,
+                None => 1,
+            };
+            ShotRange::new(start.parse().ok()?, end.parse().ok()?, step, start.len())
+        },
+    )(input)
+}
+
+/// parse a list of level items (eg {0010,0020,0030})
+pub fn parse_level_list(input: &str) -> IResult<&str, Vec<&str>> {
+    delimited(
+        tag("{"),
+        separated_nonempty_list(tag(","), parse_level_item),
+        tag("}"),
+    )(input)
+}
+
+// a tier of a level set, which is either a single item, or a range or list
+// which ends the set
+enum Tier<'a> {
+    Item(&'a str),
+    Many(Leaves<'a>),
+}
+
+fn parse_tier(input: &str) -> IResult<&str, Tier<'_>> {
+    alt((
+        map(parse_shot_range, |range| Tier::Many(Leaves::Range(range))),
+        map(parse_level_list, |list| Tier::Many(Leaves::List(list))),
+        map(parse_level_item, Tier::Item),
+    ))(input)
+}
+
+/// Return a parser for a level set, whose depth is limited by the level
+/// schema of its show. Only the lowest tier may be a range or list, so
+/// parsing stops after one.
+pub fn parse_level_set_with<'a, 's>(
+    schemas: &'s LevelSchemas,
+) -> impl Fn(&'a str) -> IResult<&'a str, LevelSet<'a>> + 's {
+    move |input: &'a str| {
+        let (mut i, show) = parse_resource(input)?;
+        let depth = schemas.for_show(show).depth();
+        let mut level = Level::show(show);
+        while level.depth() < depth {
+            let (rest, tier) = match preceded(tag("."), parse_tier)(i) {
+                Ok(result) => result,
+                Err(nom::Err::Error(_)) => break,
+                Err(err) => return Err(err),
+            };
+            match tier {
+                Tier::Item(name) => level = level.with_tier(name),
+                Tier::Many(leaves) => {
+                    return Ok((
+                        rest,
+                        LevelSet::Many {
+                            parent: level,
+                            leaves,
+                        },
+                    ))
+                }
+            }
+            i = rest;
+        }
+        Ok((i, LevelSet::One(level)))
+    }
+}
+
 /// Parse a simplified levelspec string, which may be show, show.seq, or show.seq.shot
-pub fn parse_level(input: &str) -> IResult<&str, Level> {
-    terminated(alt((parse_shot, parse_seq, parse_show)), tag("/"))(input)
+pub fn parse_level(input: &str) -> IResult<&str, Level<'_>> {
+    terminated(parse_level_noslash, tag("/"))(input)
 }
 
 /// Parse a simplified levelspec string, which may be show, show.seq, or show.seq.shot
-pub fn parse_level_noslash(input: &str) -> IResult<&str, Level> {
-    alt((parse_shot, parse_seq, parse_show))(input)
+pub fn parse_level_noslash(input: &str) -> IResult<&str, Level<'_>> {
+    parse_level_with(&LevelSchemas::default())(input)
 }
 
 #[cfg(test)]
@@ -106,6 +210,23 @@ mod tests {
             // can pase a sequence
             assert_eq!(parse_seq("dev01.rd."), Ok((".", Level::seq("dev01", "rd"))));
         }
+        #[test]
+        fn parse_seq_agrees_with_parse_level() {
+            for spec in &["dev01.rd", "dev01.1000", "dev01.rd.0001"] {
+                assert_eq!(
+                    parse_seq(spec).map(|(_, level)| level),
+                    parse_level_with(&LevelSchemas::new().with_show(
+                        "dev01",
+                        crate::level_schema::LevelSchema::new(vec!["show", "sequence"]).unwrap()
+                    ))(spec)
+                    .map(|(_, level)| level)
+                );
+            }
+            assert_eq!(
+                parse_shot("dev01.1000.0001").map(|(_, level)| level),
+                parse_level_noslash("dev01.1000.0001").map(|(_, level)| level)
+            );
+        }
     }
     mod level_item {
         use super::*;
@@ -144,6 +265,44 @@ mod tests {
             );
         }
     }
+    mod parse_level_set {
+        use super::*;
+
+        #[test]
+        fn can_parse_shot_range() {
+            assert_eq!(
+                parse_shot_range("0010-0100x10/"),
+                Ok(("/", ShotRange::new(10, 100, 10, 4).unwrap()))
+            );
+            assert_eq!(
+                parse_shot_range("1-9"),
+                Ok(("", ShotRange::new(1, 9, 1, 1).unwrap()))
+            );
+            assert!(parse_shot_range("9-1").is_err());
+        }
+        #[test]
+        fn can_parse_level_list() {
+            assert_eq!(
+                parse_level_list("{0010,0020}/"),
+                Ok(("/", vec!["0010", "0020"]))
+            );
+            assert!(parse_level_list("{}").is_err());
+        }
+        #[test]
+        fn range_ends_level_set() {
+            let schemas = LevelSchemas::default();
+            assert_eq!(
+                parse_level_set_with(&schemas)("dev01.{rd,ab}.0001"),
+                Ok((
+                    ".0001",
+                    LevelSet::Many {
+                        parent: Level::show("dev01"),
+                        leaves: Leaves::List(vec!["rd", "ab"])
+                    }
+                ))
+            );
+        }
+    }
     mod parse_level {
         use super::*;
 
@@ -175,6 +334,34 @@ mod tests {
             // what happens if we throw in a non-supported char?
         }
         #[test]
+        fn cannot_parse_level_deeper_than_schema() {
+            assert_eq!(
+                parse_level("dev01.rd.9999.x/"),
+                Err(Error((".x/", ErrorKind::Tag)))
+            );
+        }
+        #[test]
+        fn can_parse_level_with_show_schema() {
+            use crate::level_schema::LevelSchema;
+            let schemas = LevelSchemas::new()
+                .with_show(
+                    "dev02",
+                    LevelSchema::new(vec!["show", "episode", "sequence", "shot"]).unwrap(),
+                )
+                .with_show("dev03", LevelSchema::new(vec!["show", "shot"]).unwrap());
+            assert_eq!(
+                parse_level_with(&schemas)("dev02.ep01.rd.0001/"),
+                Ok((
+                    "/",
+                    Level::from_tiers(vec!["dev02", "ep01", "rd", "0001"]).unwrap()
+                ))
+            );
+            assert_eq!(
+                parse_level_with(&schemas)("dev03.0001.x"),
+                Ok((".x", Level::seq("dev03", "0001")))
+            );
+        }
+        #[test]
         fn can_parse_level_from_seq_up_to_space() {
             assert_eq!(
                 parse_level("dev01 .rd/"),
@@ -1,11 +1,19 @@
 pub mod container_name;
 pub mod department;
 pub mod hashkey;
+pub mod layer;
 pub mod level;
+pub mod pass;
+pub mod pattern;
 pub mod query;
 pub mod resource;
+pub mod resource_path;
 pub mod scheme;
 pub mod snapshot_type;
+pub mod source;
 pub mod subcontext;
+pub mod timestamp;
 pub mod uri;
 pub mod utils;
+pub mod version;
+pub mod version_req;
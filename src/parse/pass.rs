@@ -0,0 +1,7 @@
+use super::container_name::parse_name;
+use nom::IResult;
+
+/// Retrieve the render pass name given an input str
+pub fn parse_pass(input: &str) -> IResult<&str, &str> {
+    parse_name(input)
+}
@@ -0,0 +1,197 @@
+//! parse an AssetPattern
+//!
+//! A pattern follows the structure of a uri, with wildcards permitted in
+//! each component. The literal text of each component is limited to the
+//! characters its uri grammar permits.
+use crate::parse::{
+    query::{is_as_of_char, is_version_char},
+    resource::parse_resource,
+    resource_path::is_segment_char,
+    utils::is_valid_body_char,
+};
+use crate::pattern::{AssetPattern, Glob, GlobToken, TierPattern};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::none_of,
+    combinator::{map, not, opt, peek, value},
+    error::ErrorKind,
+    multi::{many0, many1, separated_nonempty_list},
+    sequence::{delimited, pair, preceded, terminated},
+    IResult,
+};
+
+// letters, digits and underscores, as permitted in names
+fn is_name_char(c: char) -> bool {
+    c.is_ascii() && is_valid_body_char(c as u8)
+}
+
+// a range of a character class (eg a-z) or a single character. A reversed
+// range (eg z-a) could never match, so fails the pattern outright
+fn parse_class_range(input: &str) -> IResult<&str, (char, char)> {
+    let (i, lo) = none_of("]")(input)?;
+    let upper: IResult<&str, char> = preceded(tag("-"), none_of("]"))(i);
+    match upper {
+        Ok((i, hi)) if lo <= hi => Ok((i, (lo, hi))),
+        Ok(_) => Err(nom::Err::Failure((input, ErrorKind::Verify))),
+        Err(_) => Ok((i, (lo, lo))),
+    }
+}
+
+// a character class (eg [a-z] or [!0-9])
+fn parse_class(input: &str) -> IResult<&str, GlobToken> {
+    map(
+        delimited(
+            tag("["),
+            pair(opt(alt((tag("!"), tag("^")))), many1(parse_class_range)),
+            tag("]"),
+        ),
+        |(negation, ranges)| GlobToken::Class {
+            negated: negation.is_some(),
+            ranges,
+        },
+    )(input)
+}
+
+/// Generate a parser for a glob whose literal text is made of characters
+/// satisfying is_literal. A ? followed by a query key and = (eg ?version=)
+/// begins a query, so ends the glob rather than matching a single character,
+/// wherever it appears
+pub fn parse_glob<'a>(is_literal: fn(char) -> bool) -> impl Fn(&'a str) -> IResult<&'a str, Glob> {
+    move |input: &'a str| {
+        let query_start = pair(take_while1(is_name_char), tag("="));
+        map(
+            many1(alt((
+                value(GlobToken::Any, tag("*")),
+                value(GlobToken::One, terminated(tag("?"), not(query_start))),
+                parse_class,
+                map(take_while1(is_literal), |s: &str| {
+                    GlobToken::Literal(s.to_string())
+                }),
+            ))),
+            Glob::new,
+        )(input)
+    }
+}
+
+/// Parse the pattern for a tier of a level. A tier of ** matches any
+/// number of tiers
+pub fn parse_tier_pattern(input: &str) -> IResult<&str, TierPattern> {
+    alt((
+        value(
+            TierPattern::AnyTiers,
+            terminated(tag("**"), peek(alt((tag("."), tag("/"))))),
+        ),
+        map(parse_glob(is_name_char), TierPattern::Tier),
+    ))(input)
+}
+
+// a query key followed by the pattern for its value, whose literal text is
+// limited by the key in the same manner as the uri
+fn parse_query_pattern(input: &str) -> IResult<&str, (String, Glob)> {
+    let (i, key) = terminated(parse_resource, tag("="))(input)?;
+    let is_literal = match key {
+        "version" => is_version_char,
+        "as_of" => is_as_of_char,
+        _ => is_name_char,
+    };
+    let (i, glob) = parse_glob(is_literal)(i)?;
+    Ok((i, (key.to_string(), glob)))
+}
+
+/// Parse an AssetPattern
+/// (eg asset://dev01.**/*/model/*/alembic_*?version=*#main)
+pub fn parse_asset_pattern(input: &str) -> IResult<&str, AssetPattern> {
+    let (i, scheme) = terminated(parse_glob(is_name_char), alt((tag("://"), tag(":/"))))(input)?;
+    let (i, level) = terminated(
+        separated_nonempty_list(tag("."), parse_tier_pattern),
+        tag("/"),
+    )(i)?;
+    let (i, path) = separated_nonempty_list(tag("/"), parse_glob(is_name_char))(i)?;
+    let (i, query) = opt(preceded(
+        tag("?"),
+        separated_nonempty_list(tag("&"), parse_query_pattern),
+    ))(i)?;
+    let (i, key) = opt(preceded(tag("#"), parse_glob(is_name_char)))(i)?;
+    let (i, resource_path) = match key {
+        Some(_) => many0(preceded(tag("/"), parse_glob(is_segment_char)))(i)?,
+        None => (i, Vec::new()),
+    };
+    Ok((
+        i,
+        AssetPattern::new(
+            scheme,
+            level,
+            path,
+            query.unwrap_or_default(),
+            key,
+            resource_path,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_stops_at_query() {
+        assert_eq!(
+            parse_glob(is_name_char)("alembic_?version=*"),
+            Ok((
+                "?version=*",
+                Glob::new(vec![GlobToken::Literal("alembic_".into())])
+            ))
+        );
+        assert_eq!(
+            parse_glob(is_name_char)("bo?/"),
+            Ok((
+                "/",
+                Glob::new(vec![GlobToken::Literal("bo".into()), GlobToken::One])
+            ))
+        );
+    }
+
+    #[test]
+    fn can_parse_class() {
+        assert_eq!(
+            parse_class("[!a-c_]"),
+            Ok((
+                "",
+                GlobToken::Class {
+                    negated: true,
+                    ranges: vec![('a', 'c'), ('_', '_')]
+                }
+            ))
+        );
+        assert_eq!(
+            parse_class("[a-]"),
+            Ok((
+                "",
+                GlobToken::Class {
+                    negated: false,
+                    ranges: vec![('a', 'a'), ('-', '-')]
+                }
+            ))
+        );
+        assert_eq!(
+            parse_class("[z-a]"),
+            Err(nom::Err::Failure(("z-a]", ErrorKind::Verify)))
+        );
+    }
+
+    #[test]
+    fn can_parse_tier_patterns() {
+        assert_eq!(parse_tier_pattern("**/"), Ok(("/", TierPattern::AnyTiers)));
+        assert_eq!(
+            parse_tier_pattern("rd*."),
+            Ok((
+                ".",
+                TierPattern::Tier(Glob::new(vec![
+                    GlobToken::Literal("rd".into()),
+                    GlobToken::Any
+                ]))
+            ))
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::errors::UriComponent;
 use crate::parse::resource::parse_resource;
-use nom::bytes::complete::tag;
+use crate::parse::utils::valid_body1_parser;
+use nom::bytes::complete::{tag, take_while1};
 use nom::multi::many0;
 use nom::sequence::preceded;
 use nom::IResult;
@@ -30,18 +32,108 @@ impl<'a> From<QueryPair<'a>> for OwnedQueryPair {
     }
 }
 
-pub fn parse_and<'a>(input: &'a str) -> IResult<&'a str, QueryPair<'a>> {
-    let (i, key) = preceded(tag("&"), parse_resource)(input)?;
-    let (i, value) = preceded(tag("="), parse_resource)(i)?;
+pub(crate) const EXPECTED_VERSION: &str =
+    "a version: current, latest, a number (eg 0003), a constraint (eg >=3,<7), a relative selector (eg latest-1) or a tag";
+pub(crate) const EXPECTED_AS_OF: &str =
+    "a timestamp: seconds since the epoch, a date (eg 2020-03-01) or a date and time (eg 2020-03-01T12:30:00Z)";
+
+/// The query keys which the parser interprets itself, rather than passing
+/// them through as query parameters
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum QueryKey {
+    Version,
+    AsOf,
+}
+
+impl QueryKey {
+    /// Every reserved key, in the order in which they are described
+    pub(crate) const ALL: [QueryKey; 2] = [QueryKey::Version, QueryKey::AsOf];
+
+    /// Whether keys other than the reserved keys are accepted, and passed
+    /// through as query parameters
+    pub(crate) const OTHERS_ACCEPTED: bool = true;
+
+    /// Look up the reserved key, if any, named by the supplied key
+    pub(crate) fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|k| k.key() == key)
+    }
+
+    /// The key as written in the query
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Self::Version => "version",
+            Self::AsOf => "as_of",
+        }
+    }
+
+    /// The component of the uri which the key sets
+    pub(crate) fn component(self) -> UriComponent {
+        match self {
+            Self::Version => UriComponent::Version,
+            Self::AsOf => UriComponent::AsOf,
+        }
+    }
+
+    /// Describe what the parser expects to find for the value of the key
+    pub(crate) fn expected(self) -> &'static str {
+        match self {
+            Self::Version => EXPECTED_VERSION,
+            Self::AsOf => EXPECTED_AS_OF,
+        }
+    }
+}
+
+/// Parse the value of a query pair. Unlike keys, values may start with a
+/// digit (eg version=1)
+pub fn parse_query_value(input: &str) -> IResult<&str, &str> {
+    valid_body1_parser(input)
+}
+
+/// Parse the value of the version query pair. In addition to the characters
+/// permitted in other values, this admits those used to express constraints
+/// (eg >=3,<7 or 3..7 or ^12) and relative selectors (eg latest-1 or current+2)
+pub fn parse_version_value(input: &str) -> IResult<&str, &str> {
+    take_while1(is_version_char)(input)
+}
+
+// characters permitted in the value of the version query pair
+pub(crate) fn is_version_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_<>=,.^+-".contains(c)
+}
+
+/// Parse the value of the as_of query pair. In addition to the characters
+/// permitted in other values, this admits those used in ISO 8601 dates and
+/// times (eg 2020-03-01T12:30:00Z)
+pub fn parse_as_of_value(input: &str) -> IResult<&str, &str> {
+    take_while1(is_as_of_char)(input)
+}
+
+// characters permitted in the value of the as_of query pair
+pub(crate) fn is_as_of_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-:".contains(c)
+}
+
+// parse key=value, where the permitted value depends upon the key
+fn parse_pair(input: &str) -> IResult<&str, QueryPair<'_>> {
+    let (i, key) = parse_resource(input)?;
+    let (i, value) = match QueryKey::from_key(key) {
+        Some(QueryKey::Version) => preceded(tag("="), parse_version_value)(i)?,
+        Some(QueryKey::AsOf) => preceded(tag("="), parse_as_of_value)(i)?,
+        None if QueryKey::OTHERS_ACCEPTED => preceded(tag("="), parse_query_value)(i)?,
+        None => return Err(nom::Err::Error((input, nom::error::ErrorKind::Tag))),
+    };
     Ok((i, QueryPair::from(key, value)))
 }
 
+pub fn parse_and<'a>(input: &'a str) -> IResult<&'a str, QueryPair<'a>> {
+    preceded(tag("&"), parse_pair)(input)
+}
+
 pub fn parse_query<'a>(input: &'a str) -> IResult<&'a str, Vec<QueryPair<'a>>> {
-    let (i, key) = preceded(tag("?"), parse_resource)(input)?;
-    let (i, value) = preceded(tag("="), parse_resource)(i)?;
+    let (i, first) = preceded(tag("?"), parse_pair)(input)?;
     let (i, mut pairs) = many0(parse_and)(i)?;
     let mut rval: Vec<QueryPair> = Vec::with_capacity(pairs.len() + 1);
-    rval.push(QueryPair::from(key, value));
+    rval.push(first);
     rval.append(&mut pairs);
     Ok((i, rval))
 }
@@ -72,6 +164,61 @@ mod tests {
                 ))
             )
         }
+        #[test]
+        fn can_parse_version_constraint() {
+            assert_eq!(
+                parse_query("?version=>=3,<7&server=organic"),
+                Ok((
+                    "",
+                    vec![
+                        QueryPair::from("version", ">=3,<7"),
+                        QueryPair::from("server", "organic")
+                    ]
+                ))
+            )
+        }
+        #[test]
+        fn constraint_characters_are_only_permitted_in_version() {
+            assert_eq!(
+                parse_query("?server=^12"),
+                Err(nom::Err::Error(("^12", nom::error::ErrorKind::Alpha)))
+            )
+        }
+        #[test]
+        fn can_parse_numeric_value() {
+            assert_eq!(
+                parse_query("?version=0003"),
+                Ok(("", vec![QueryPair::from("version", "0003")]))
+            )
+        }
+        #[test]
+        fn can_parse_as_of_timestamp() {
+            assert_eq!(
+                parse_query("?as_of=2020-03-01T12:30:00Z&server=organic"),
+                Ok((
+                    "",
+                    vec![
+                        QueryPair::from("as_of", "2020-03-01T12:30:00Z"),
+                        QueryPair::from("server", "organic")
+                    ]
+                ))
+            )
+        }
+    }
+    mod query_key {
+        use super::*;
+
+        #[test]
+        fn can_look_up_reserved_keys() {
+            assert_eq!(QueryKey::from_key("version"), Some(QueryKey::Version));
+            assert_eq!(QueryKey::from_key("as_of"), Some(QueryKey::AsOf));
+            assert_eq!(QueryKey::from_key("server"), None);
+            assert_eq!(QueryKey::AsOf.component(), UriComponent::AsOf);
+            assert_eq!(
+                parse_query("?server=organic").is_ok(),
+                QueryKey::OTHERS_ACCEPTED
+            );
+        }
     }
     mod parse_and {
         use super::*;
@@ -0,0 +1,77 @@
+//! parse the optional resource path which follows the key
+//!
+//! Given the assumption that we are here:
+//! #key/resource/path
+//!
+//! Each segment of the path must start with a letter or digit, and may
+//! contain letters, digits, underscores, hyphens and periods.
+
+use nom::bytes::complete::{tag, take_while};
+use nom::multi::many1;
+use nom::sequence::preceded;
+use nom::{character, IResult};
+
+pub(crate) fn is_segment_char(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || chr == '_' || chr == '-' || chr == '.'
+}
+
+/// parse a single segment of a resource path, not including the leading slash
+pub fn parse_path_segment(input: &str) -> IResult<&str, &str> {
+    let (i, r1) = character::complete::alphanumeric1(input)?;
+    let (i, r2) = take_while(is_segment_char)(i)?;
+    Ok((i, &input[0..r1.len() + r2.len()]))
+}
+
+/// parse one or more slash separated segments of a resource path
+/// eg ```/textures/diffuse.1001.tx```
+pub fn parse_resource_path(input: &str) -> IResult<&str, Vec<&str>> {
+    many1(preceded(tag("/"), parse_path_segment))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+    use nom::Err::Error;
+
+    mod parse_path_segment {
+        use super::*;
+
+        #[test]
+        fn can_parse_file_name() {
+            assert_eq!(
+                parse_path_segment("diffuse_main-v2.1001.tx"),
+                Ok(("", "diffuse_main-v2.1001.tx"))
+            );
+        }
+        #[test]
+        fn can_parse_up_to_slash() {
+            assert_eq!(parse_path_segment("textures/a"), Ok(("/a", "textures")));
+        }
+        #[test]
+        fn cannot_start_with_period() {
+            assert_eq!(
+                parse_path_segment(".."),
+                Err(Error(("..", ErrorKind::AlphaNumeric)))
+            );
+        }
+    }
+    mod parse_resource_path {
+        use super::*;
+
+        #[test]
+        fn can_parse_multiple_segments() {
+            assert_eq!(
+                parse_resource_path("/textures/diffuse.tx"),
+                Ok(("", vec!["textures", "diffuse.tx"]))
+            );
+        }
+        #[test]
+        fn requires_at_least_one_segment() {
+            assert_eq!(
+                parse_resource_path("/"),
+                Err(Error(("", ErrorKind::AlphaNumeric)))
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use crate::parse::resource::parse_resource;
 use crate::scheme::Scheme;
-use nom::{branch::alt, bytes::complete::tag, sequence::terminated, IResult};
+use crate::scheme_registry::SchemeRegistry;
+use nom::{branch::alt, bytes::complete::tag, combinator::map_opt, sequence::terminated, IResult};
 use std::str::FromStr;
 
 /// Parse the scheme for the set of asset manager routes
@@ -10,13 +12,54 @@ pub fn scheme_parser(input: &str) -> IResult<&str, &str> {
     )(input)
 }
 
-/// Generate a Scheme instance from an input string
+/// Generate a Scheme instance from an input string, accepting the default
+/// aliases (inst and rnd) and the single slash form of the separator (eg
+/// asset:/). The canonical scheme is returned
 pub fn parse_scheme(input: &str) -> IResult<&str, Scheme> {
+    parse_scheme_with(&SchemeRegistry::default())(input)
+}
+
+/// Generate a Scheme instance from an input string, accepting only the
+/// canonical name of a built-in scheme followed by ://
+pub fn parse_scheme_strict(input: &str) -> IResult<&str, Scheme> {
     let (i, r) = scheme_parser(input)?;
     // should be able to unwrap here because we have successfully parsed
     // the scheme
     Ok((i, Scheme::from_str(r).unwrap()))
 }
+
+/// Generate a parser for the schemes registered with the supplied registry,
+/// which accepts their aliases and the single slash form of the separator
+/// (eg inst:/). The canonical scheme is returned
+pub fn parse_scheme_with<'a, 's>(
+    registry: &'s SchemeRegistry,
+) -> impl Fn(&'a str) -> IResult<&'a str, Scheme> + 's {
+    move |input: &'a str| {
+        map_opt(
+            terminated(parse_resource, alt((tag("://"), tag(":/")))),
+            |name: &str| {
+                registry
+                    .lookup(name)
+                    .map(|definition| definition.scheme().clone())
+            },
+        )(input)
+    }
+}
+
+/// Generate a parser for the schemes registered with the supplied registry,
+/// which accepts only their canonical names followed by ://
+pub fn parse_scheme_strict_with<'a, 's>(
+    registry: &'s SchemeRegistry,
+) -> impl Fn(&'a str) -> IResult<&'a str, Scheme> + 's {
+    move |input: &'a str| {
+        map_opt(terminated(parse_resource, tag("://")), |name: &str| {
+            registry
+                .get(name)
+                .map(|definition| definition.scheme().clone())
+        })(input)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,4 +79,70 @@ mod tests {
             assert_eq!(scheme_parser("frender://").is_err(), true);
         }
     }
+
+    mod parse_scheme {
+        use super::*;
+
+        #[test]
+        fn accepts_aliases_and_single_slash() {
+            assert_eq!(
+                parse_scheme("inst://dev01"),
+                Ok(("dev01", Scheme::Instance))
+            );
+            assert_eq!(parse_scheme("rnd:/dev01"), Ok(("dev01", Scheme::Render)));
+            assert_eq!(parse_scheme("asset:/dev01"), Ok(("dev01", Scheme::Asset)));
+        }
+
+        #[test]
+        fn strict_rejects_aliases_and_single_slash() {
+            assert_eq!(
+                parse_scheme_strict("instance://dev01"),
+                Ok(("dev01", Scheme::Instance))
+            );
+            assert!(parse_scheme_strict("inst://dev01").is_err());
+            assert!(parse_scheme_strict("asset:/dev01").is_err());
+        }
+    }
+
+    mod parse_scheme_with {
+        use super::*;
+        use crate::scheme_registry::SchemeDefinition;
+
+        #[test]
+        fn can_parse_registered_schemes() {
+            let registry = SchemeRegistry::new()
+                .with_scheme(SchemeDefinition::new("texture").unwrap())
+                .unwrap();
+            assert_eq!(
+                parse_scheme_with(&registry)("texture://dev01"),
+                Ok(("dev01", Scheme::named("texture")))
+            );
+            assert_eq!(
+                parse_scheme_with(&registry)("plate://dev01"),
+                Ok(("dev01", Scheme::Plate))
+            );
+        }
+
+        #[test]
+        fn can_parse_registered_aliases() {
+            let registry = SchemeRegistry::new()
+                .with_scheme(SchemeDefinition::new("texture").unwrap())
+                .unwrap()
+                .with_alias("tex", &Scheme::named("texture"))
+                .unwrap();
+            assert_eq!(
+                parse_scheme_with(&registry)("tex:/dev01"),
+                Ok(("dev01", Scheme::named("texture")))
+            );
+            assert!(parse_scheme_strict_with(&registry)("tex://dev01").is_err());
+            assert!(parse_scheme_with(&registry.without_aliases())("inst://dev01").is_err());
+        }
+
+        #[test]
+        fn unregistered_schemes_are_not_supported() {
+            let registry = SchemeRegistry::new();
+            assert!(parse_scheme_with(&registry)("texture://").is_err());
+            assert!(parse_scheme_with(&registry)("renderful://").is_err());
+        }
+    }
 }
@@ -0,0 +1,20 @@
+use super::container_name::parse_name;
+use nom::IResult;
+
+/// Retrieve the name of the source asset of an instance given an input str.
+/// Whether the source follows the asset name rule is checked by the caller,
+/// as a `SchemeRegistry` may change the rule
+pub fn parse_source(input: &str) -> IResult<&str, &str> {
+    parse_name(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_source_asset_name() {
+        assert_eq!(parse_source("thanos/anim"), Ok(("anim", "thanos")));
+        assert!(parse_source("1thanos/anim").is_err());
+    }
+}
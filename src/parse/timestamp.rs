@@ -0,0 +1,90 @@
+//! Parse timestamps
+//!
+//! A timestamp may take one of the following forms:
+//! - seconds since the unix epoch (eg 1583020800)
+//! - a date (eg 2020-03-01), meaning midnight UTC
+//! - a date and time, with an optional Z (eg 2020-03-01T12:30:00Z)
+
+use crate::timestamp::Timestamp;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while_m_n};
+use nom::character::complete::digit1;
+use nom::combinator::{map_opt, map_res, opt};
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+
+// parse exactly two digits
+fn parse_two_digits(input: &str) -> IResult<&str, u32> {
+    map_res(
+        take_while_m_n(2, 2, |c: char| c.is_ascii_digit()),
+        str::parse,
+    )(input)
+}
+
+/// parse a date (eg 2020-03-01) into its year, month and day
+pub fn parse_date(input: &str) -> IResult<&str, (u32, u32, u32)> {
+    tuple((
+        map_res(digit1, str::parse),
+        preceded(tag("-"), parse_two_digits),
+        preceded(tag("-"), parse_two_digits),
+    ))(input)
+}
+
+/// parse a time (eg T12:30:00Z) into its hour, minute and second
+pub fn parse_time(input: &str) -> IResult<&str, (u32, u32, u32)> {
+    preceded(
+        tag("T"),
+        terminated(
+            tuple((
+                parse_two_digits,
+                preceded(tag(":"), parse_two_digits),
+                preceded(tag(":"), parse_two_digits),
+            )),
+            opt(tag("Z")),
+        ),
+    )(input)
+}
+
+/// parse a timestamp, either as an ISO 8601 date and optional time, or as
+/// seconds since the epoch
+pub fn parse_timestamp(input: &str) -> IResult<&str, Timestamp> {
+    alt((
+        map_opt(
+            tuple((parse_date, opt(parse_time))),
+            |((year, month, day), time)| {
+                let (hour, minute, second) = time.unwrap_or((0, 0, 0));
+                Timestamp::from_ymd_hms(year, month, day, hour, minute, second)
+            },
+        ),
+        map_res(digit1, |secs: &str| secs.parse().map(Timestamp::from_secs)),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_date() {
+        assert_eq!(parse_date("2020-03-01"), Ok(("", (2020, 3, 1))));
+        assert!(parse_date("2020-3-01").is_err());
+    }
+
+    #[test]
+    fn can_parse_time() {
+        assert_eq!(parse_time("T12:30:05Z"), Ok(("", (12, 30, 5))));
+        assert_eq!(parse_time("T12:30:05"), Ok(("", (12, 30, 5))));
+    }
+
+    #[test]
+    fn can_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2020-03-01T12:30:00Z&lod=hi"),
+            Ok(("&lod=hi", Timestamp::from_secs(1_583_065_800)))
+        );
+        assert_eq!(
+            parse_timestamp("1583065800"),
+            Ok(("", Timestamp::from_secs(1_583_065_800)))
+        );
+    }
+}
@@ -1,49 +1,429 @@
 //! Uri provides the entrypoint parsing function, used to transform a str into
 //! an AssetModel
-use crate::assetmodel::AssetModel;
-use crate::errors::AmuriError;
+use crate::assetmodel::{AssetModel, OwnedAssetModel};
+use crate::config::ParseConfig;
+use crate::errors::{AmuriError, ParseDiagnostic, UriComponent};
+use crate::level::Level;
 use crate::parse::{
-    container_name::parse_name, department::parse_department, hashkey::parse_hashtag,
-    level::parse_level, query::parse_query, scheme::parse_scheme,
-    snapshot_type::parse_snapshot_type, subcontext::parse_subcontext,
+    container_name::parse_name,
+    department::parse_department,
+    hashkey::parse_hashtag,
+    layer::parse_layer,
+    level::{parse_level_set_with, parse_level_with},
+    pass::parse_pass,
+    query::{parse_query, QueryKey, EXPECTED_AS_OF, EXPECTED_VERSION},
+    resource_path::parse_resource_path,
+    scheme::{parse_scheme_strict_with, parse_scheme_with},
+    snapshot_type::parse_snapshot_type,
+    source::parse_source,
+    subcontext::parse_subcontext,
 };
+use crate::scheme::Scheme;
+use crate::timestamp::Timestamp;
 use crate::version::Version;
-use nom::combinator::{all_consuming, opt};
-use nom::sequence::tuple;
-/// parse hashtag from str, which should generally take the form
-/// ```#key```
-pub fn parse_uri(input: &str) -> Result<AssetModel, crate::errors::AmuriError> {
-    let (_i, (scheme, level, name, dept, subcontext, snaptype, query, key)) =
-        all_consuming(tuple((
-            parse_scheme,
-            parse_level,
-            parse_name,
-            parse_department,
-            parse_subcontext,
-            parse_snapshot_type,
-            opt(parse_query),
-            opt(parse_hashtag),
-        )))(input)
-        .map_err(|err| AmuriError::UriParsingError {
-            cause: format!("{:?}", err),
-        })?;
-    let mut version = None;
-    if query.is_some() {
-        for querypair in query.unwrap() {
-            if querypair.key == "version" {
-                version = Some(Version::from_str(querypair.value)?);
-                break;
+use crate::vocabulary::{is_known, suggest, Vocabulary};
+use nom::bytes::complete::tag;
+use nom::sequence::terminated;
+use nom::{IResult, Offset};
+use std::str::FromStr;
+
+const EXPECTED_SCHEME: &str =
+    "a registered scheme (eg asset, instance, render or plate) followed by ://";
+const EXPECTED_CANONICAL_SCHEME: &str =
+    "the canonical name of a registered scheme (eg instance rather than inst) followed by ://";
+const EXPECTED_LEVEL: &str =
+    "a level (eg show, show.seq or show.seq.shot, per the show's level schema) followed by /";
+const EXPECTED_LEVEL_SET: &str =
+    "a level whose lowest tier may be a range (eg 0001-0100x10) or list (eg {0010,0020}), followed by /";
+const EXPECTED_NAME: &str = "a name starting with a letter, followed by /";
+const EXPECTED_DEPARTMENT: &str = "a department starting with a letter, followed by /";
+const EXPECTED_SUBCONTEXT: &str = "a subcontext starting with a letter, followed by /";
+const EXPECTED_SOURCE: &str = "a source asset name starting with a letter, followed by /";
+const EXPECTED_LAYER: &str = "a layer starting with a letter, followed by /";
+const EXPECTED_PASS: &str = "a pass starting with a letter, followed by /";
+const EXPECTED_SNAPSHOT_TYPE: &str = "a snapshot type starting with a letter";
+const EXPECTED_QUERY: &str = "query parameters of the form ?key=value&key=value";
+const EXPECTED_KEY: &str = "a key starting with a letter";
+const EXPECTED_RESOURCE_PATH: &str = "path segments of the form /segment";
+const EXPECTED_END: &str = "end of uri";
+const EXPECTED_KNOWN_DEPARTMENT: &str = "a known department";
+const EXPECTED_KNOWN_SUBCONTEXT: &str = "a known subcontext";
+const EXPECTED_KNOWN_SNAPSHOT_TYPE: &str = "a known snapshot type";
+
+/// Describe what the parser expects to find for a component of the uri
+pub(crate) fn expected(component: UriComponent) -> &'static str {
+    match component {
+        UriComponent::Scheme => EXPECTED_SCHEME,
+        UriComponent::Level => EXPECTED_LEVEL,
+        UriComponent::Name => EXPECTED_NAME,
+        UriComponent::Source => EXPECTED_SOURCE,
+        UriComponent::Department => EXPECTED_DEPARTMENT,
+        UriComponent::Subcontext => EXPECTED_SUBCONTEXT,
+        UriComponent::Layer => EXPECTED_LAYER,
+        UriComponent::Pass => EXPECTED_PASS,
+        UriComponent::SnapshotType => EXPECTED_SNAPSHOT_TYPE,
+        UriComponent::Version => EXPECTED_VERSION,
+        UriComponent::AsOf => EXPECTED_AS_OF,
+        UriComponent::Query => EXPECTED_QUERY,
+        UriComponent::Key => EXPECTED_KEY,
+        UriComponent::ResourcePath => EXPECTED_RESOURCE_PATH,
+    }
+}
+
+// the text of the component starting at input, up to the next delimiter
+fn component_text(input: &str) -> &str {
+    let end = input.find(|c| "/?&#:".contains(c)).unwrap_or(input.len());
+    &input[..end]
+}
+
+// Attach suggestions, drawn from candidates, for the component starting at
+// input to a parse error
+fn with_suggestions<S: AsRef<str>>(err: AmuriError, input: &str, candidates: &[S]) -> AmuriError {
+    match err {
+        AmuriError::UriParsingError(diagnostic) => AmuriError::UriParsingError(
+            diagnostic.with_suggestions(suggest(component_text(input), candidates)),
+        ),
+        err => err,
+    }
+}
+
+// the names of the schemes registered with the config
+fn scheme_names(config: &ParseConfig) -> Vec<String> {
+    config.schemes().names().map(String::from).collect()
+}
+
+// Parse the scheme at the start of the uri. A strict config rejects aliases
+// and the single slash separator, suggesting the canonical scheme in their
+// place
+fn parse_scheme_component<'a>(
+    input: &'a str,
+    config: &ParseConfig,
+) -> Result<(&'a str, Scheme), AmuriError> {
+    let schemes = config.schemes();
+    if !config.is_strict() {
+        return parse_component(
+            input,
+            input,
+            UriComponent::Scheme,
+            EXPECTED_SCHEME,
+            parse_scheme_with(schemes),
+        )
+        .map_err(|err| with_suggestions(err, input, &scheme_names(config)));
+    }
+    parse_component(
+        input,
+        input,
+        UriComponent::Scheme,
+        EXPECTED_SCHEME,
+        parse_scheme_strict_with(schemes),
+    )
+    .map_err(|err| match parse_scheme_with(schemes)(input) {
+        Ok((_, scheme)) => AmuriError::UriParsingError(
+            ParseDiagnostic::new(input, 0, UriComponent::Scheme, EXPECTED_CANONICAL_SCHEME)
+                .with_suggestions(vec![scheme.to_string()]),
+        ),
+        Err(_) => with_suggestions(err, input, &scheme_names(config)),
+    })
+}
+
+// Convert an error raised for a value which the grammar accepted (eg a
+// version which does not parse) into a ParseDiagnostic pointing at the value.
+// The value must be a slice of the uri
+fn value_error(
+    uri: &str,
+    value: &str,
+    component: UriComponent,
+    expected: &'static str,
+    err: AmuriError,
+) -> AmuriError {
+    AmuriError::UriParsingError(
+        ParseDiagnostic::new(uri, uri.offset(value), component, expected)
+            .with_note(err.to_string()),
+    )
+}
+
+// Verify that the component value, which starts at input, is in the list of
+// known values
+fn check_known(
+    uri: &str,
+    input: &str,
+    value: &str,
+    component: UriComponent,
+    expected: &'static str,
+    known: &[String],
+) -> Result<(), AmuriError> {
+    if is_known(known, value) {
+        return Ok(());
+    }
+    Err(AmuriError::UriParsingError(
+        ParseDiagnostic::new(uri, uri.len() - input.len(), component, expected)
+            .with_suggestions(suggest(value, known)),
+    ))
+}
+
+// Run the parser for a single component of the uri, converting a failure into
+// a ParseDiagnostic which records where the failure occurred
+fn parse_component<'a, O, F>(
+    uri: &'a str,
+    input: &'a str,
+    component: UriComponent,
+    expected: &'static str,
+    parser: F,
+) -> Result<(&'a str, O), AmuriError>
+where
+    F: Fn(&'a str) -> IResult<&'a str, O>,
+{
+    parser(input).map_err(|err| {
+        let rest = match err {
+            nom::Err::Error((rest, _)) | nom::Err::Failure((rest, _)) => rest,
+            nom::Err::Incomplete(_) => input,
+        };
+        AmuriError::UriParsingError(ParseDiagnostic::new(
+            uri,
+            uri.len() - rest.len(),
+            component,
+            expected,
+        ))
+    })
+}
+
+/// Parse a uri of the form
+/// ```scheme://level/name/department/subcontext/type?version=value#key/resource/path```
+/// into an AssetModel. The components between the level and the query
+/// follow the route layout of the scheme (see `RouteLayout`)
+pub fn parse_uri(input: &str) -> Result<AssetModel<'_>, crate::errors::AmuriError> {
+    parse_uri_with_config(input, &ParseConfig::default())
+}
+
+/// Parse a uri into an AssetModel, requiring that the department, subcontext
+/// and snapshot type appear in the supplied vocabulary. Errors for those
+/// components carry suggestions drawn from the vocabulary.
+pub fn parse_uri_with_vocabulary<'a>(
+    input: &'a str,
+    vocabulary: &Vocabulary,
+) -> Result<AssetModel<'a>, crate::errors::AmuriError> {
+    parse_uri_with_config(
+        input,
+        &ParseConfig::new().with_vocabulary(vocabulary.clone()),
+    )
+}
+
+/// Parse a uri into an AssetModel as directed by the supplied config. The
+/// department, subcontext and snapshot type must appear in the config's
+/// vocabulary, the level must follow the level schema of its show, and the
+/// scheme must be registered with the config's scheme registry, which
+/// supplies its name rule and required components. Unless the config is
+/// strict, the scheme may be given by alias (eg inst://) or with a single
+/// slash (eg asset:/); the model always reports the canonical scheme.
+pub fn parse_uri_with_config<'a>(
+    input: &'a str,
+    config: &ParseConfig,
+) -> Result<AssetModel<'a>, crate::errors::AmuriError> {
+    let (i, scheme) = parse_scheme_component(input, config)?;
+    let (i, level) = parse_component(
+        input,
+        i,
+        UriComponent::Level,
+        EXPECTED_LEVEL,
+        terminated(parse_level_with(config.level_schemas()), tag("/")),
+    )?;
+    parse_container(input, i, scheme, level, config)
+}
+
+/// Parse a uri whose level may be a level set (eg dev01.rd.0001-0100 or
+/// dev01.rd.{0010,0020}) into an OwnedAssetModel for each level in the set
+pub fn parse_uri_set(input: &str) -> Result<Vec<OwnedAssetModel>, crate::errors::AmuriError> {
+    parse_uri_set_with_config(input, &ParseConfig::default())
+}
+
+/// Parse a uri whose level may be a level set into an OwnedAssetModel for
+/// each level in the set, as directed by the supplied config
+pub fn parse_uri_set_with_config(
+    input: &str,
+    config: &ParseConfig,
+) -> Result<Vec<OwnedAssetModel>, crate::errors::AmuriError> {
+    let (i, scheme) = parse_scheme_component(input, config)?;
+    let (i, level_set) = parse_component(
+        input,
+        i,
+        UriComponent::Level,
+        EXPECTED_LEVEL_SET,
+        terminated(parse_level_set_with(config.level_schemas()), tag("/")),
+    )?;
+    // the remaining components are shared by each level in the set
+    let level = Level::show(level_set.show_name());
+    let model = OwnedAssetModel::from(parse_container(input, i, scheme, level, config)?);
+    Ok(level_set
+        .expand()?
+        .into_iter()
+        .map(|level| model.with_level(level))
+        .collect())
+}
+
+// Parse a component of the path of the route, other than the name, starting
+// at i. Components with a vocabulary must appear in it
+fn parse_path_component<'a>(
+    input: &'a str,
+    i: &'a str,
+    component: UriComponent,
+    vocabulary: &Vocabulary,
+) -> Result<(&'a str, &'a str), AmuriError> {
+    let expected = expected(component);
+    let parser: fn(&str) -> IResult<&str, &str> = match component {
+        UriComponent::Source => parse_source,
+        UriComponent::Department => parse_department,
+        UriComponent::Subcontext => parse_subcontext,
+        UriComponent::Layer => parse_layer,
+        UriComponent::Pass => parse_pass,
+        // the layout ends with the snapshot type
+        _ => parse_snapshot_type,
+    };
+    let known = match component {
+        UriComponent::Department => Some((vocabulary.departments(), EXPECTED_KNOWN_DEPARTMENT)),
+        UriComponent::Subcontext => Some((vocabulary.subcontexts(), EXPECTED_KNOWN_SUBCONTEXT)),
+        UriComponent::SnapshotType => {
+            Some((vocabulary.snapshot_types(), EXPECTED_KNOWN_SNAPSHOT_TYPE))
+        }
+        _ => None,
+    };
+    match known {
+        Some((known, expected_known)) => {
+            let (rest, value) = parse_component(input, i, component, expected, parser)
+                .map_err(|err| with_suggestions(err, i, known))?;
+            check_known(input, i, value, component, expected_known, known)?;
+            Ok((rest, value))
+        }
+        None => parse_component(input, i, component, expected, parser),
+    }
+}
+
+// the number of components of the path starting at input, which ends at the
+// query or key
+fn path_len(input: &str) -> usize {
+    let end = input.find(['?', '#']).unwrap_or(input.len());
+    input[..end].split('/').count()
+}
+
+// Parse the components of the uri which follow the level, starting at i
+fn parse_container<'a>(
+    input: &'a str,
+    i: &'a str,
+    scheme: Scheme,
+    level: Level<'a>,
+    config: &ParseConfig,
+) -> Result<AssetModel<'a>, crate::errors::AmuriError> {
+    let schemes = config.schemes();
+    let (mut i, name) = parse_component(input, i, UriComponent::Name, EXPECTED_NAME, parse_name)?;
+    // the name is followed by the remaining components of the scheme's
+    // layout. Where the path is too short for every optional component,
+    // those at the end of the layout are omitted
+    let layout = schemes.layout(&scheme);
+    let mut optional = path_len(i).saturating_sub(layout.required_len() - 1);
+    let mut route = Vec::new();
+    for component in layout.components().iter().skip(1).copied() {
+        if layout.is_optional(component) {
+            if optional == 0 {
+                continue;
             }
+            optional -= 1;
+        }
+        let (rest, value) = parse_path_component(input, i, component, config.vocabulary())?;
+        if component == UriComponent::Source {
+            // the source names an asset, so follows the asset name rule
+            schemes
+                .validate_name(&Scheme::Asset, value)
+                .map_err(|err| {
+                    value_error(
+                        input,
+                        value,
+                        component,
+                        schemes.name_rule(&Scheme::Asset).expected(),
+                        err,
+                    )
+                })?;
         }
+        route.push((component, value));
+        i = rest;
     }
-    Ok(AssetModel::new(
-        scheme, level, name, dept, subcontext, snaptype, version, key,
-    ))
+    let snaptype = route.last().map_or("", |(_, value)| *value);
+    let mut last = UriComponent::SnapshotType;
+    let mut query = Vec::new();
+    if i.starts_with('?') {
+        let (rest, pairs) =
+            parse_component(input, i, UriComponent::Query, EXPECTED_QUERY, parse_query)?;
+        i = rest;
+        query = pairs;
+        last = UriComponent::Query;
+    }
+    let mut key = None;
+    if i.starts_with('#') {
+        let (rest, hashtag) =
+            parse_component(input, i, UriComponent::Key, EXPECTED_KEY, parse_hashtag)?;
+        i = rest;
+        key = Some(hashtag);
+        last = UriComponent::Key;
+    }
+    let mut resource_path = Vec::new();
+    if key.is_some() && i.starts_with('/') {
+        let (rest, path) = parse_component(
+            input,
+            i,
+            UriComponent::ResourcePath,
+            EXPECTED_RESOURCE_PATH,
+            parse_resource_path,
+        )?;
+        i = rest;
+        resource_path = path;
+        last = UriComponent::ResourcePath;
+    }
+    if !i.is_empty() {
+        return Err(AmuriError::UriParsingError(ParseDiagnostic::new(
+            input,
+            input.len() - i.len(),
+            last,
+            EXPECTED_END,
+        )));
+    }
+    schemes.validate_name(&scheme, name).map_err(|err| {
+        value_error(
+            input,
+            name,
+            UriComponent::Name,
+            schemes.name_rule(&scheme).expected(),
+            err,
+        )
+    })?;
+    let mut model = AssetModel::minimal(scheme, level, name, snaptype)
+        .with_route_layout(layout)
+        .with_key(key)
+        .with_resource_path(resource_path);
+    for (component, value) in route {
+        model = model.with_component(component, value)?;
+    }
+    // when a parameter is repeated, the first occurrence wins
+    for querypair in query.into_iter().rev() {
+        let invalid = |key: QueryKey, err| {
+            value_error(input, querypair.value, key.component(), key.expected(), err)
+        };
+        model = match QueryKey::from_key(querypair.key) {
+            Some(key @ QueryKey::Version) => model.with_version(Some(
+                Version::from_str(querypair.value).map_err(|err| invalid(key, err))?,
+            )),
+            Some(key @ QueryKey::AsOf) => model.with_as_of(Some(
+                Timestamp::from_str(querypair.value).map_err(|err| invalid(key, err))?,
+            )),
+            None => model.with_query_param(querypair.key, querypair.value)?,
+        };
+    }
+    schemes.check_required(&model)?;
+    Ok(model)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::level::OwnedLevel;
+    use crate::parse::container_name::NameRule;
     #[test]
     fn can_parse_asset_uri_with_version_and_key() {
         let uri = parse_uri("asset://dev01/bob/model/hi/maya_model?version=current#main");
@@ -91,12 +471,666 @@ mod tests {
         );
         assert_eq!(uri, expect);
     }
+    #[test]
+    fn preserves_query_params() {
+        let uri =
+            parse_uri("asset://dev01/bob/model/hi/maya_model?server=organic&version=3&lod=hi")
+                .unwrap();
+        assert_eq!(uri.version(), Some(&Version::number(3)));
+        assert_eq!(uri.query_param("server"), Some("organic"));
+        assert_eq!(uri.query_param("lod"), Some("hi"));
+        assert_eq!(uri.query_param("version"), None);
+        assert_eq!(
+            uri.to_string(),
+            "asset://dev01/bob/model/hi/maya_model?version=3&lod=hi&server=organic"
+        );
+    }
+
+    #[test]
+    fn can_parse_version_constraint() {
+        for (uri, display) in &[
+            ("?version=>=3,<7", "?version=>=3,<7"),
+            ("?version=3..7", "?version=>=3,<7"),
+            ("?version=^12", "?version=>=12"),
+        ] {
+            let uri = format!("asset://dev01/bob/model/hi/maya_model{}", uri);
+            let model = parse_uri(&uri).unwrap();
+            assert!(matches!(model.version(), Some(Version::Req(_))));
+            let expect = format!("asset://dev01/bob/model/hi/maya_model{}", display);
+            assert_eq!(model.to_string(), expect);
+        }
+    }
+
+    #[test]
+    fn can_parse_version_selectors() {
+        for (uri, display) in &[
+            ("?version=latest-1", "?version=latest-1"),
+            ("?version=previous", "?version=current-1"),
+            ("?version=approved", "?version=approved"),
+        ] {
+            let uri = format!("asset://dev01/bob/model/hi/maya_model{}", uri);
+            let model = parse_uri(&uri).unwrap();
+            let expect = format!("asset://dev01/bob/model/hi/maya_model{}", display);
+            assert_eq!(model.to_string(), expect);
+        }
+    }
+
+    #[test]
+    fn can_parse_as_of() {
+        let uri =
+            parse_uri("asset://dev01/bob/model/hi/maya_model?as_of=2020-03-01&version=approved")
+                .unwrap();
+        assert_eq!(uri.as_of(), Some(Timestamp::from_secs(1_583_020_800)));
+        assert_eq!(uri.query_param("as_of"), None);
+        assert_eq!(
+            uri.to_string(),
+            "asset://dev01/bob/model/hi/maya_model?version=approved&as_of=2020-03-01T00:00:00Z"
+        );
+        let diag = diagnostic("asset://dev01/bob/model/hi/maya_model?as_of=2020-02-30");
+        assert_eq!(diag.component, UriComponent::AsOf);
+        assert_eq!(diag.offset, 44);
+        assert_eq!(diag.found, "2020-02-30");
+        assert_eq!(diag.expected, EXPECTED_AS_OF);
+        let note = AmuriError::TimestampParsingFailure("2020-02-30".into()).to_string();
+        assert_eq!(diag.note.as_deref(), Some(note.as_str()));
+    }
+
+    #[test]
+    fn preserves_version_padding() {
+        let uri = "asset://dev01/bob/model/hi/maya_model?version=0003";
+        assert_eq!(parse_uri(uri).unwrap().to_string(), uri);
+    }
+
+    #[test]
+    fn first_repeated_query_param_wins() {
+        let uri =
+            parse_uri("asset://dev01/bob/model/hi/maya_model?version=3&version=4&a=b&a=c").unwrap();
+        assert_eq!(uri.version(), Some(&Version::number(3)));
+        assert_eq!(uri.query_param("a"), Some("b"));
+    }
+
+    #[test]
+    fn can_parse_resource_path_following_key() {
+        let uri =
+            parse_uri("asset://dev01/bob/surface/hi/textures?version=2#main/diffuse/color.1001.tx")
+                .unwrap();
+        assert_eq!(uri.key(), Some("main"));
+        assert_eq!(uri.resource_path(), &["diffuse", "color.1001.tx"]);
+    }
+
+    #[test]
+    fn resource_path_requires_key() {
+        assert!(parse_uri("asset://dev01/bob/surface/hi/textures/diffuse").is_err());
+        assert!(parse_uri("asset://dev01/bob/surface/hi/textures#main/").is_err());
+    }
+
+    #[test]
+    fn enforces_scheme_name_rule() {
+        assert!(parse_uri("instance://dev01/bob1/anim/hi/alembic_cache").is_ok());
+        let diag = diagnostic("asset://dev01/bob1/model/hi/maya_model");
+        assert_eq!(diag.component, UriComponent::Name);
+        assert_eq!(diag.offset, 14);
+        assert_eq!(diag.found, "bob1");
+        let note = AmuriError::NameRuleViolation {
+            scheme: Scheme::Asset,
+            name: "bob1".into(),
+            rule: "name must not end with a digit",
+        }
+        .to_string();
+        assert_eq!(diag.note.as_deref(), Some(note.as_str()));
+        let diag = diagnostic("instance://dev01/bob/anim/hi/alembic_cache");
+        assert_eq!(diag.offset, 17);
+        assert_eq!(diag.expected, NameRule::TrailingDigit.expected());
+        assert_eq!(
+            diag.note,
+            Some("Invalid instance name 'bob': name must end with a digit".into())
+        );
+    }
+
+    #[test]
+    fn reports_invalid_version_value() {
+        let diag = diagnostic("asset://dev01/bob/model/hi/maya_model?lod=hi&version=>=3,<");
+        assert_eq!(diag.component, UriComponent::Version);
+        assert_eq!(diag.offset, 53);
+        assert_eq!(diag.found, ">=3,<");
+        assert_eq!(diag.expected, EXPECTED_VERSION);
+        assert!(diag.note.is_some());
+    }
+
+    mod route_layouts {
+        use super::*;
+
+        #[test]
+        fn plate_has_no_department_or_subcontext() {
+            let uri = "plate://dev01.rd.0001/bg01/exr?version=2";
+            let model = parse_uri(uri).unwrap();
+            assert_eq!(model.name(), "bg01");
+            assert_eq!(model.component(UriComponent::Department), None);
+            assert_eq!(model.component(UriComponent::Subcontext), None);
+            assert_eq!(model.snapshot_type(), "exr");
+            assert_eq!(model.to_string(), uri);
+            assert!(parse_uri("plate://dev01.rd.0001/bg01/comp/hi/exr").is_err());
+        }
+
+        #[test]
+        fn render_has_layer_and_pass() {
+            let uri = "render://dev01.rd.0001/bob/light/hi/beauty/diffuse/exr#main";
+            let model = parse_uri(uri).unwrap();
+            assert_eq!(model.department(), "light");
+            assert_eq!(model.layer(), Some("beauty"));
+            assert_eq!(model.pass(), Some("diffuse"));
+            assert_eq!(model.to_string(), uri);
+            let diag = diagnostic("render://dev01.rd.0001/bob/light/hi/exr");
+            assert_eq!(diag.component, UriComponent::Layer);
+        }
+
+        #[test]
+        fn instance_has_source_asset() {
+            let uri = "instance://dev01.rd.0001/thanos1/thanos/anim/hi/alembic_cache";
+            let model = parse_uri(uri).unwrap();
+            assert_eq!(model.source(), Some("thanos"));
+            assert_eq!(model.department(), "anim");
+            assert_eq!(model.to_string(), uri);
+            let diag = diagnostic("instance://dev01.rd.0001/thanos1/thanos2/anim/hi/alembic_cache");
+            assert_eq!(diag.component, UriComponent::Source);
+            assert_eq!(diag.offset, 33);
+        }
+
+        #[test]
+        fn instance_source_is_optional() {
+            let uri = "instance://dev01.rd.0001/thanos1/anim/hi/alembic_cache?version=current#main";
+            let model = parse_uri(uri).unwrap();
+            assert_eq!(model.source(), None);
+            assert_eq!(model.department(), "anim");
+            assert_eq!(model.subcontext(), "hi");
+            assert_eq!(model.to_string(), uri);
+            let diag = diagnostic("instance://dev01.rd.0001/thanos1/anim/alembic_cache");
+            assert_eq!(diag.component, UriComponent::Subcontext);
+        }
+
+        #[test]
+        fn can_register_layout() {
+            use crate::route::RouteLayout;
+            use crate::scheme_registry::{SchemeDefinition, SchemeRegistry};
+            let layout = RouteLayout::new(vec![
+                UriComponent::Name,
+                UriComponent::Department,
+                UriComponent::SnapshotType,
+            ])
+            .unwrap();
+            let config = ParseConfig::new().with_schemes(
+                SchemeRegistry::new()
+                    .with_scheme(
+                        SchemeDefinition::from(crate::scheme::Scheme::Plate).with_layout(layout),
+                    )
+                    .unwrap(),
+            );
+            let model = parse_uri_with_config("plate://dev01/bg01/comp/exr", &config).unwrap();
+            assert_eq!(model.department(), "comp");
+            assert_eq!(model.to_string(), "plate://dev01/bg01/comp/exr");
+        }
+    }
+
+    mod registered_schemes {
+        use super::*;
+        use crate::parse::container_name::NameRule;
+        use crate::scheme_registry::{SchemeDefinition, SchemeRegistry};
+
+        fn config() -> ParseConfig {
+            ParseConfig::new().with_schemes(
+                SchemeRegistry::new()
+                    .with_scheme(
+                        SchemeDefinition::new("texture")
+                            .unwrap()
+                            .with_name_rule(NameRule::NoTrailingDigit)
+                            .with_required(UriComponent::Key),
+                    )
+                    .unwrap(),
+            )
+        }
+
+        #[test]
+        fn can_parse_registered_scheme() {
+            let uri = "texture://dev01/bob/lookdev/hi/tx#diffuse";
+            let model = parse_uri_with_config(uri, &config()).unwrap();
+            assert_eq!(
+                model.container_type(),
+                &crate::scheme::Scheme::named("texture")
+            );
+            assert_eq!(model.to_string(), uri);
+            assert!(parse_uri(uri).is_err());
+        }
+
+        #[test]
+        fn enforces_registered_name_rule_and_required_components() {
+            match parse_uri_with_config("texture://dev01/bob1/lookdev/hi/tx#diffuse", &config()) {
+                Err(AmuriError::UriParsingError(diag)) => {
+                    assert_eq!(diag.component, UriComponent::Name);
+                    assert_eq!(diag.expected, NameRule::NoTrailingDigit.expected());
+                    assert_eq!(
+                        diag.note,
+                        Some("Invalid texture name 'bob1': name must not end with a digit".into())
+                    );
+                }
+                other => panic!("expected parse failure, got {:?}", other),
+            }
+            assert_eq!(
+                parse_uri_with_config("texture://dev01/bob/lookdev/hi/tx", &config()),
+                Err(AmuriError::MissingComponent(UriComponent::Key))
+            );
+        }
+
+        #[test]
+        fn suggests_registered_schemes() {
+            let diag = match parse_uri_with_config("textur://dev01/bob/lookdev/hi/tx", &config()) {
+                Err(AmuriError::UriParsingError(diag)) => diag,
+                other => panic!("expected parse failure, got {:?}", other),
+            };
+            assert_eq!(diag.suggestions, vec!["texture".to_string()]);
+        }
+    }
+
+    mod scheme_aliases {
+        use super::*;
+
+        #[test]
+        fn aliases_canonicalize() {
+            let model =
+                parse_uri("inst:/dev01.rd.9999/thanos1/anim/hi/alembic_cache?version=current#main")
+                    .unwrap();
+            assert_eq!(model.container_type(), &crate::scheme::Scheme::Instance);
+            assert_eq!(
+                model.to_string(),
+                "instance://dev01.rd.9999/thanos1/anim/hi/alembic_cache?version=current#main"
+            );
+            assert_eq!(
+                parse_uri("rnd://dev01/bob/light/hi/beauty/diffuse/exr").map(|m| m.to_string()),
+                Ok("render://dev01/bob/light/hi/beauty/diffuse/exr".to_string())
+            );
+            assert_eq!(
+                parse_uri("asset:/dev01/bob/model/hi/maya_model"),
+                parse_uri("asset://dev01/bob/model/hi/maya_model")
+            );
+        }
+
+        #[test]
+        fn can_configure_aliases() {
+            use crate::scheme_registry::SchemeRegistry;
+            let config = ParseConfig::new().with_schemes(
+                SchemeRegistry::new()
+                    .without_aliases()
+                    .with_alias("a", &crate::scheme::Scheme::Asset)
+                    .unwrap(),
+            );
+            assert!(parse_uri_with_config("a://dev01/bob/model/hi/maya_model", &config).is_ok());
+            assert!(parse_uri_with_config("inst://dev01/bob1/anim/hi/abc", &config).is_err());
+        }
+
+        #[test]
+        fn strict_mode_rejects_aliases() {
+            let config = ParseConfig::new().with_strict(true);
+            let uri = "inst://dev01/bob1/anim/hi/abc";
+            assert_eq!(
+                parse_uri_with_config(uri, &config),
+                Err(AmuriError::UriParsingError(
+                    ParseDiagnostic::new(uri, 0, UriComponent::Scheme, EXPECTED_CANONICAL_SCHEME)
+                        .with_suggestions(vec!["instance".into()])
+                ))
+            );
+            assert!(
+                parse_uri_with_config("asset:/dev01/bob/model/hi/maya_model", &config).is_err()
+            );
+            assert!(
+                parse_uri_with_config("asset://dev01/bob/model/hi/maya_model", &config).is_ok()
+            );
+            match parse_uri_with_config("assetf://dev01/bob/model/hi/maya_model", &config) {
+                Err(AmuriError::UriParsingError(diag)) => {
+                    assert_eq!(diag.expected, EXPECTED_SCHEME);
+                    assert_eq!(diag.suggestions, vec!["asset".to_string()]);
+                }
+                other => panic!("expected parse failure, got {:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn can_handle_bad_scheme() {
         let uri = parse_uri("assetf://dev01/bob/model/hi/maya_model");
-        let expect = Err(AmuriError::UriParsingError {
-            cause: "Error((\"f://dev01/bob/model/hi/maya_model\", Tag))".to_string(),
-        });
+        let expect = Err(AmuriError::UriParsingError(ParseDiagnostic {
+            input: "assetf://dev01/bob/model/hi/maya_model".into(),
+            offset: 0,
+            component: UriComponent::Scheme,
+            expected: EXPECTED_SCHEME,
+            found: "assetf".into(),
+            suggestions: vec!["asset".into()],
+            note: None,
+        }));
         assert_eq!(uri, expect);
     }
+
+    fn diagnostic(uri: &str) -> ParseDiagnostic {
+        match parse_uri(uri) {
+            Err(AmuriError::UriParsingError(diag)) => diag,
+            other => panic!("expected parse failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_failing_component_and_offset() {
+        let diag = diagnostic("asset://dev01/bob/mo-del/hi/maya_model");
+        assert_eq!(diag.component, UriComponent::Department);
+        assert_eq!(diag.offset, 20);
+        assert_eq!(diag.found, "-del");
+
+        let diag = diagnostic("asset://dev01/bob/model/hi");
+        assert_eq!(diag.component, UriComponent::Subcontext);
+        assert_eq!(diag.offset, 26);
+        assert_eq!(diag.found, "end of uri");
+
+        let diag = diagnostic("asset://dev01/bob/model/hi/maya_model?version=");
+        assert_eq!(diag.component, UriComponent::Query);
+
+        let diag = diagnostic("asset://dev01/bob/model/hi/maya_model#1main");
+        assert_eq!(diag.component, UriComponent::Key);
+        assert_eq!(diag.offset, 38);
+    }
+
+    #[test]
+    fn suggests_misspelled_scheme() {
+        let err = parse_uri("rendr://dev01/beauty/lighting/hi/exr").unwrap_err();
+        assert_eq!(err.suggestions(), &["render".to_string()]);
+        let err = parse_uri("assset://dev01/bob/model/hi/maya_model").unwrap_err();
+        assert_eq!(err.suggestions(), &["asset".to_string()]);
+    }
+
+    fn vocabulary() -> Vocabulary {
+        Vocabulary::new()
+            .with_departments(vec!["model", "anim", "rig"])
+            .with_subcontexts(vec!["hi", "lo"])
+            .with_snapshot_types(vec!["maya_model", "alembic_model"])
+    }
+
+    #[test]
+    fn can_parse_with_vocabulary() {
+        let vocab = vocabulary();
+        assert!(parse_uri_with_vocabulary("asset://dev01/bob/model/hi/maya_model", &vocab).is_ok());
+    }
+
+    #[test]
+    fn rejects_component_missing_from_vocabulary() {
+        let vocab = vocabulary();
+        let err =
+            parse_uri_with_vocabulary("asset://dev01/bob/modle/hi/maya_model", &vocab).unwrap_err();
+        assert_eq!(err.suggestions(), &["model".to_string()]);
+        match err {
+            AmuriError::UriParsingError(diag) => {
+                assert_eq!(diag.component, UriComponent::Department);
+                assert_eq!(diag.offset, 18);
+                assert_eq!(diag.expected, EXPECTED_KNOWN_DEPARTMENT);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        let err =
+            parse_uri_with_vocabulary("asset://dev01/bob/model/hi/maya_modl", &vocab).unwrap_err();
+        assert_eq!(err.suggestions(), &["maya_model".to_string()]);
+    }
+
+    #[test]
+    fn suggests_vocabulary_when_component_fails_to_parse() {
+        let vocab = vocabulary();
+        let err =
+            parse_uri_with_vocabulary("asset://dev01/bob/model/h!/maya_model", &vocab).unwrap_err();
+        assert_eq!(err.suggestions(), &["hi".to_string(), "lo".to_string()]);
+    }
+
+    #[test]
+    fn level_follows_show_schema() {
+        use crate::level_schema::{LevelSchema, LevelSchemas};
+        let config = ParseConfig::new().with_level_schemas(LevelSchemas::new().with_show(
+            "dev02",
+            LevelSchema::new(vec!["show", "episode", "sequence", "shot"]).unwrap(),
+        ));
+        let uri = "asset://dev02.ep01.rd.0001/bob/model/hi/maya_model";
+        let model = parse_uri_with_config(uri, &config).unwrap();
+        assert_eq!(model.level().tiers(), &["dev02", "ep01", "rd", "0001"]);
+        assert_eq!(model.to_string(), uri);
+        let err = parse_uri_with_config(
+            "asset://dev01.ep01.rd.0001/bob/model/hi/maya_model",
+            &config,
+        )
+        .unwrap_err();
+        match err {
+            AmuriError::UriParsingError(diag) => {
+                assert_eq!(diag.component, UriComponent::Level);
+                assert_eq!(diag.offset, 21);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn can_parse_uri_set() {
+        let models =
+            parse_uri_set("asset://dev01.rd.0010-0030x10/bob/model/hi/maya_model?version=2#main")
+                .unwrap();
+        assert_eq!(
+            models.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "asset://dev01.rd.0010/bob/model/hi/maya_model?version=2#main",
+                "asset://dev01.rd.0020/bob/model/hi/maya_model?version=2#main",
+                "asset://dev01.rd.0030/bob/model/hi/maya_model?version=2#main",
+            ]
+        );
+        let models = parse_uri_set("asset://dev01.rd.{0010,0050}/bob/model/hi/maya_model").unwrap();
+        assert_eq!(models[1].level(), &OwnedLevel::shot("dev01", "rd", "0050"));
+        let models = parse_uri_set("asset://dev01.rd/bob/model/hi/maya_model").unwrap();
+        assert_eq!(models.len(), 1);
+    }
+
+    #[test]
+    fn uri_set_reports_invalid_level_set() {
+        match parse_uri_set("asset://dev01.{rd,ab}.0001/bob/model/hi/maya_model") {
+            Err(AmuriError::UriParsingError(diag)) => {
+                assert_eq!(diag.component, UriComponent::Level);
+                assert_eq!(diag.offset, 21);
+                assert_eq!(diag.expected, EXPECTED_LEVEL_SET);
+            }
+            other => panic!("expected parse failure, got {:?}", other),
+        }
+        assert!(parse_uri("asset://dev01.rd.0001-0100/bob/model/hi/maya_model").is_err());
+    }
+
+    #[test]
+    fn uri_set_rejects_oversized_level_set() {
+        assert!(matches!(
+            parse_uri_set("asset://dev01.rd.0-4000000000/bob/model/hi/maya_model"),
+            Err(AmuriError::LevelSetTooLarge {
+                size: 4_000_000_001,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reports_trailing_input() {
+        let diag = diagnostic("asset://dev01/bob/model/hi/maya_model?version=1&lod");
+        assert_eq!(diag.component, UriComponent::Query);
+        assert_eq!(diag.expected, EXPECTED_END);
+        assert_eq!(diag.found, "&");
+    }
+
+    #[test]
+    fn can_render_readable_message() {
+        let diag = diagnostic("asset://dev01/bob/model/h!/maya_model");
+        assert_eq!(
+            diag.render(),
+            format!(
+                "invalid subcontext: expected {}, found '!'\n  {}\n  {}^",
+                EXPECTED_SUBCONTEXT,
+                "asset://dev01/bob/model/h!/maya_model",
+                " ".repeat(25)
+            )
+        );
+    }
+
+    mod roundtrip {
+        use super::*;
+        use crate::level::Level;
+        use crate::parse::container_name::NameRule;
+        use crate::version::{Anchor, VersionNumber};
+        use crate::version_req::{Comparator, Op, VersionReq};
+        use proptest::prelude::*;
+        use strum::IntoEnumIterator;
+
+        const NAME: &str = "[a-zA-Z]([a-zA-Z0-9_]{0,8}[a-zA-Z0-9])?";
+        const ASSET_NAME: &str = "[a-zA-Z]([a-zA-Z0-9_]{0,8}[a-zA-Z])?";
+        const INSTANCE_NAME: &str = "[a-zA-Z][a-zA-Z0-9_]{0,8}[0-9]";
+        const LEVEL_ITEM: &str = "[a-zA-Z0-9]([a-zA-Z0-9_]{0,6}[a-zA-Z0-9])?";
+        const RESERVED: [&str; 3] = ["current", "latest", "previous"];
+        const SEGMENT: &str = "[a-zA-Z0-9][a-zA-Z0-9_.-]{0,8}";
+
+        fn scheme() -> impl Strategy<Value = Scheme> {
+            prop::sample::select(Scheme::iter().collect::<Vec<_>>())
+        }
+
+        // a name which satisfies the rule by construction
+        fn name(rule: NameRule) -> BoxedStrategy<String> {
+            match rule {
+                NameRule::NoTrailingDigit => ASSET_NAME.boxed(),
+                NameRule::TrailingDigit => INSTANCE_NAME.boxed(),
+                NameRule::Any => NAME.boxed(),
+            }
+        }
+
+        fn scheme_and_name() -> impl Strategy<Value = (Scheme, String)> {
+            scheme().prop_flat_map(|scheme| {
+                let rule = scheme.name_rule();
+                (Just(scheme), name(rule))
+            })
+        }
+
+        fn comparator() -> impl Strategy<Value = Comparator> {
+            let ops = vec![Op::Exact, Op::Greater, Op::GreaterEq, Op::Less, Op::LessEq];
+            (prop::sample::select(ops), any::<u32>()).prop_map(|(op, v)| Comparator::new(op, v))
+        }
+
+        fn version() -> impl Strategy<Value = Option<Version>> {
+            prop::option::of(prop_oneof![
+                Just(Version::Current),
+                Just(Version::Latest),
+                (any::<u32>(), 0..8usize)
+                    .prop_map(|(v, w)| Version::Number(VersionNumber::with_width(v, w))),
+                prop::collection::vec(comparator(), 1..3)
+                    .prop_map(|c| Version::Req(VersionReq::new(c))),
+                (
+                    prop::sample::select(vec![Anchor::Current, Anchor::Latest]),
+                    any::<i32>()
+                )
+                    .prop_map(|(anchor, offset)| Version::Relative { anchor, offset }),
+                NAME.prop_filter("reserved", |n| !RESERVED.contains(&n.as_str()))
+                    .prop_map(Version::Tag),
+            ])
+        }
+
+        proptest! {
+            #[test]
+            fn display_then_parse_yields_equal_model(
+                (scheme, name) in scheme_and_name(),
+                show in NAME,
+                sequence in NAME,
+                shot in LEVEL_ITEM,
+                depth in 0..3usize,
+                source in prop::option::of(name(NameRule::NoTrailingDigit)),
+                dept in NAME,
+                subcontext in NAME,
+                layer in NAME,
+                pass in NAME,
+                snaptype in NAME,
+                version in version(),
+                as_of in prop::option::of((0..253_402_300_800u64).prop_map(Timestamp::from_secs)),
+                query in prop::collection::btree_map(NAME, LEVEL_ITEM, 0..3),
+                key in prop::option::of(NAME),
+                resource_path in prop::collection::vec(SEGMENT, 0..3),
+            ) {
+                let level = match depth {
+                    0 => Level::show(&show),
+                    1 => Level::seq(&show, &sequence),
+                    _ => Level::shot(&show, &sequence, &shot),
+                };
+                let layout = scheme.route_layout();
+                let mut model = AssetModel::minimal(scheme, level, &name, &snaptype)
+                    .with_version(version)
+                    .with_as_of(as_of)
+                    .with_key(key.as_deref());
+                for (component, value) in [
+                    (UriComponent::Source, source.as_ref()),
+                    (UriComponent::Department, Some(&dept)),
+                    (UriComponent::Subcontext, Some(&subcontext)),
+                    (UriComponent::Layer, Some(&layer)),
+                    (UriComponent::Pass, Some(&pass)),
+                ] {
+                    if let (true, Some(value)) = (layout.contains(component), value) {
+                        model = model.with_component(component, value).unwrap();
+                    }
+                }
+                if key.is_some() {
+                    model = model.with_resource_path(resource_path.iter().map(String::as_str));
+                }
+                for (k, v) in query.iter().filter(|(k, _)| k.as_str() != "version" && k.as_str() != "as_of") {
+                    model = model.with_query_param(k, v).unwrap();
+                }
+                let uri = model.to_string();
+                let parsed = parse_uri(&uri);
+                prop_assert_eq!(parsed.as_ref().map(ToString::to_string), Ok(uri.clone()));
+                prop_assert_eq!(parsed, Ok(model));
+            }
+
+            #[test]
+            fn models_changed_via_setters_roundtrip(
+                initial in scheme(),
+                (scheme, name) in scheme_and_name(),
+                show in NAME,
+                source in prop::option::of(name(NameRule::NoTrailingDigit)),
+                dept in NAME,
+                subcontext in NAME,
+                layer in NAME,
+                pass in NAME,
+                snaptype in NAME,
+                version in version(),
+                key in prop::option::of(NAME),
+            ) {
+                // start from another scheme, whose components may fall
+                // outside of the final scheme's route
+                let model = AssetModel::new(
+                    initial,
+                    Level::show(&show),
+                    &name,
+                    "dept",
+                    "sub",
+                    &snaptype,
+                    version,
+                    key.as_deref(),
+                )
+                .with_source("src")
+                .with_layer("layer")
+                .with_pass("pass")
+                .with_container_type(scheme)
+                .with_department(&dept)
+                .with_subcontext(&subcontext)
+                .with_layer(&layer)
+                .with_pass(&pass);
+                let model = match source {
+                    Some(ref source) => model.with_source(source),
+                    None if model.route_layout().is_optional(UriComponent::Source) => {
+                        model.without_component(UriComponent::Source).unwrap()
+                    }
+                    None => model,
+                };
+                let uri = model.to_string();
+                let parsed = parse_uri(&uri);
+                prop_assert_eq!(parsed.as_ref().map(ToString::to_string), Ok(uri.clone()));
+                prop_assert_eq!(parsed, Ok(model));
+            }
+        }
+    }
 }
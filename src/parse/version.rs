@@ -0,0 +1,97 @@
+//! Parse relative version selectors
+//!
+//! A relative selector offsets from either the current or latest version by
+//! a number of available versions (eg latest-1, current+2). `previous` is
+//! shorthand for current-1.
+
+use crate::version::{Anchor, Version};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{digit1, one_of};
+use nom::combinator::{map, map_res};
+use nom::sequence::{pair, tuple};
+use nom::IResult;
+use std::convert::TryFrom;
+
+/// parse the anchor a relative selector offsets from
+pub fn parse_anchor(input: &str) -> IResult<&str, Anchor> {
+    alt((
+        map(tag("current"), |_| Anchor::Current),
+        map(tag("latest"), |_| Anchor::Latest),
+    ))(input)
+}
+
+/// parse a signed offset (eg -1 or +2)
+pub fn parse_offset(input: &str) -> IResult<&str, i32> {
+    map_res(
+        pair(one_of("+-"), digit1),
+        |(sign, digits): (char, &str)| {
+            // parse as i64 so that i32::MIN survives negation
+            let n = digits.parse::<i64>().map_err(|_| ())?;
+            i32::try_from(if sign == '-' { -n } else { n }).map_err(|_| ())
+        },
+    )(input)
+}
+
+/// parse a relative version selector
+pub fn parse_relative_version(input: &str) -> IResult<&str, Version> {
+    alt((
+        map(tuple((parse_anchor, parse_offset)), |(anchor, offset)| {
+            Version::Relative { anchor, offset }
+        }),
+        map(tag("previous"), |_| Version::previous()),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_offset_from_latest() {
+        assert_eq!(
+            parse_relative_version("latest-1"),
+            Ok((
+                "",
+                Version::Relative {
+                    anchor: Anchor::Latest,
+                    offset: -1
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn can_parse_offset_from_current() {
+        assert_eq!(
+            parse_relative_version("current+2"),
+            Ok((
+                "",
+                Version::Relative {
+                    anchor: Anchor::Current,
+                    offset: 2
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn can_parse_previous() {
+        assert_eq!(
+            parse_relative_version("previous"),
+            Ok(("", Version::previous()))
+        );
+    }
+
+    #[test]
+    fn can_parse_extreme_offsets() {
+        assert_eq!(parse_offset("-2147483648"), Ok(("", i32::MIN)));
+        assert!(parse_offset("+2147483648").is_err());
+    }
+
+    #[test]
+    fn offset_is_required() {
+        assert!(parse_relative_version("latest").is_err());
+        assert!(parse_relative_version("latest-").is_err());
+    }
+}
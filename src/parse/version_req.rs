@@ -0,0 +1,146 @@
+//! Parse version constraint expressions
+//!
+//! A constraint may take one of the following forms:
+//! - a comma separated list of comparators (eg >=3,<7)
+//! - a range, which excludes its end (eg 3..7) or includes it (eg 3..=7)
+//! - a caret, meaning the version or newer (eg ^12)
+
+use crate::version_req::{Comparator, Op, VersionReq};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_res, opt};
+use nom::multi::separated_nonempty_list;
+use nom::sequence::{pair, preceded, tuple};
+use nom::IResult;
+
+/// parse a version number
+pub fn parse_version_number(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// parse a comparison operator
+pub fn parse_op(input: &str) -> IResult<&str, Op> {
+    alt((
+        map(tag(">="), |_| Op::GreaterEq),
+        map(tag("<="), |_| Op::LessEq),
+        map(tag(">"), |_| Op::Greater),
+        map(tag("<"), |_| Op::Less),
+        map(tag("="), |_| Op::Exact),
+    ))(input)
+}
+
+/// parse an operator followed by a version number (eg >=3)
+pub fn parse_comparator(input: &str) -> IResult<&str, Comparator> {
+    map(pair(parse_op, parse_version_number), |(op, version)| {
+        Comparator::new(op, version)
+    })(input)
+}
+
+/// parse a range (eg 3..7 or 3..=7)
+pub fn parse_range(input: &str) -> IResult<&str, VersionReq> {
+    map(
+        tuple((
+            parse_version_number,
+            tag(".."),
+            opt(tag("=")),
+            parse_version_number,
+        )),
+        |(start, _, inclusive, end)| VersionReq::range(start, end, inclusive.is_some()),
+    )(input)
+}
+
+/// parse a caret (eg ^12)
+pub fn parse_caret(input: &str) -> IResult<&str, VersionReq> {
+    map(
+        preceded(tag("^"), parse_version_number),
+        VersionReq::at_least,
+    )(input)
+}
+
+/// parse a version constraint expression
+pub fn parse_version_req(input: &str) -> IResult<&str, VersionReq> {
+    alt((
+        parse_range,
+        parse_caret,
+        map(
+            separated_nonempty_list(tag(","), parse_comparator),
+            VersionReq::new,
+        ),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+    use nom::Err::Error;
+
+    mod parse_comparator {
+        use super::*;
+
+        #[test]
+        fn can_parse_each_op() {
+            assert_eq!(
+                parse_comparator(">=3"),
+                Ok(("", Comparator::new(Op::GreaterEq, 3)))
+            );
+            assert_eq!(
+                parse_comparator("<=3"),
+                Ok(("", Comparator::new(Op::LessEq, 3)))
+            );
+            assert_eq!(
+                parse_comparator(">3"),
+                Ok(("", Comparator::new(Op::Greater, 3)))
+            );
+            assert_eq!(
+                parse_comparator("<3"),
+                Ok(("", Comparator::new(Op::Less, 3)))
+            );
+            assert_eq!(
+                parse_comparator("=3"),
+                Ok(("", Comparator::new(Op::Exact, 3)))
+            );
+        }
+        #[test]
+        fn requires_version_number() {
+            assert_eq!(parse_comparator(">=x"), Err(Error(("x", ErrorKind::Digit))));
+        }
+    }
+    mod parse_version_req {
+        use super::*;
+
+        #[test]
+        fn can_parse_comparator_list() {
+            assert_eq!(
+                parse_version_req(">=3,<7"),
+                Ok((
+                    "",
+                    VersionReq::new(vec![
+                        Comparator::new(Op::GreaterEq, 3),
+                        Comparator::new(Op::Less, 7)
+                    ])
+                ))
+            );
+        }
+        #[test]
+        fn can_parse_range() {
+            assert_eq!(
+                parse_version_req("3..7"),
+                Ok(("", VersionReq::range(3, 7, false)))
+            );
+            assert_eq!(
+                parse_version_req("3..=7"),
+                Ok(("", VersionReq::range(3, 7, true)))
+            );
+        }
+        #[test]
+        fn can_parse_caret() {
+            assert_eq!(parse_version_req("^12"), Ok(("", VersionReq::at_least(12))));
+        }
+        #[test]
+        fn version_number_must_fit() {
+            assert!(parse_version_req(">=4294967296").is_err());
+        }
+    }
+}
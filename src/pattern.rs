@@ -0,0 +1,636 @@
+//! Wildcard patterns which select asset models
+//!
+//! An AssetPattern is written like a uri, but each component may contain
+//! wildcards (eg asset://dev01/*/model/*/alembic_*?version=*):
+//! - `*` matches any run of characters within a component
+//! - `?` matches a single character. A `?` followed by a query key and `=`
+//!   (eg `alembic_?lod=hi`) always begins the query instead; `[!/]`, which
+//!   also matches any single character, may be used in its place
+//! - `[abc]`, `[a-z]` and `[!a-z]` match a single character in, or not in,
+//!   the class
+//! - `**`, as a tier of the level, matches any number of tiers, including
+//!   none (eg dev01.** matches dev01, dev01.rd and dev01.rd.0001)
+//!
+//! The path between the level and the query is matched component by
+//! component against the model's route, so the pattern must have as many
+//! components as the route. Query parameters, the key and the resource path
+//! constrain a model only if the pattern names them; a named query parameter
+//! or key must be present on the model.
+//!
+//! Each wildcard captures the text it matched, in pattern order. A `**`
+//! captures its tiers joined by periods.
+use crate::assetmodel::AssetModel;
+use crate::config::ParseConfig;
+use crate::errors::AmuriError;
+use crate::parse::pattern::parse_asset_pattern;
+use crate::route::PATH_COMPONENTS;
+use nom::combinator::all_consuming;
+use std::fmt;
+use std::str::FromStr;
+
+/// A single element of a Glob
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum GlobToken {
+    /// Literal text
+    Literal(String),
+    /// Any run of characters (`*`)
+    Any,
+    /// Any single character (`?`)
+    One,
+    /// A single character in (or, if negated, not in) one of the inclusive
+    /// ranges (eg `[a-z]` or `[!0-9]`)
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl GlobToken {
+    // test whether a single character token matches the character
+    fn matches_char(&self, c: char) -> bool {
+        match self {
+            Self::One => true,
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GlobToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(text) => write!(f, "{}", text),
+            Self::Any => write!(f, "*"),
+            Self::One => write!(f, "?"),
+            Self::Class { negated, ranges } => {
+                write!(f, "[{}", if *negated { "!" } else { "" })?;
+                for (lo, hi) in ranges {
+                    if lo == hi {
+                        write!(f, "{}", lo)?;
+                    } else {
+                        write!(f, "{}-{}", lo, hi)?;
+                    }
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A wildcard pattern for a single component
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Glob {
+    tokens: Vec<GlobToken>,
+}
+
+impl Glob {
+    /// constructor function
+    pub fn new(tokens: Vec<GlobToken>) -> Self {
+        Self { tokens }
+    }
+
+    /// Retrieve the tokens
+    pub fn tokens(&self) -> &[GlobToken] {
+        &self.tokens
+    }
+
+    /// Retrieve the text of the glob if it contains no wildcards
+    pub fn as_literal(&self) -> Option<String> {
+        self.tokens
+            .iter()
+            .map(|token| match token {
+                GlobToken::Literal(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Option<String>>()
+    }
+
+    /// Test whether the glob matches the entirety of the text
+    pub fn matches(&self, text: &str) -> bool {
+        self.captures(text).is_some()
+    }
+
+    /// Retrieve the text matched by each wildcard, if the glob matches the
+    /// entirety of the text
+    pub fn captures(&self, text: &str) -> Option<Vec<String>> {
+        let mut captures = Vec::new();
+        if self.match_into(text, &mut captures) {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+
+    // match the text, appending captures on success and leaving them
+    // untouched on failure
+    fn match_into(&self, text: &str, captures: &mut Vec<String>) -> bool {
+        let len = captures.len();
+        if match_tokens(&self.tokens, text, captures) {
+            true
+        } else {
+            captures.truncate(len);
+            false
+        }
+    }
+}
+
+// Match tokens against the entirety of the text, appending the text matched
+// by each wildcard to the captures on success
+fn match_tokens(tokens: &[GlobToken], text: &str, captures: &mut Vec<String>) -> bool {
+    let mut matcher = Matcher {
+        tokens,
+        text,
+        failed: vec![false; (tokens.len() + 1) * (text.len() + 1)],
+        spans: Vec::new(),
+    };
+    if !matcher.match_from(0, 0) {
+        return false;
+    }
+    captures.extend(
+        matcher
+            .spans
+            .into_iter()
+            .map(|(start, end)| text[start..end].to_string()),
+    );
+    true
+}
+
+// Matches tokens against text. Runs of characters are matched greedily,
+// backtracking as required. Whether the remaining tokens match the remaining
+// text depends only upon where each starts, so the states which fail are
+// recorded and never explored twice, bounding the work by the number of
+// tokens times the length of the text
+struct Matcher<'a> {
+    tokens: &'a [GlobToken],
+    text: &'a str,
+    // indexed by token * (text.len() + 1) + offset
+    failed: Vec<bool>,
+    // the byte range of the text matched by each wildcard so far
+    spans: Vec<(usize, usize)>,
+}
+
+impl<'a> Matcher<'a> {
+    // match the tokens from token onwards against the text from offset
+    // onwards, leaving the spans untouched on failure
+    fn match_from(&mut self, token: usize, offset: usize) -> bool {
+        let state = token * (self.text.len() + 1) + offset;
+        if self.failed[state] {
+            return false;
+        }
+        let len = self.spans.len();
+        let rest = &self.text[offset..];
+        let matched = match self.tokens.get(token) {
+            None => rest.is_empty(),
+            Some(GlobToken::Literal(literal)) => {
+                rest.starts_with(literal.as_str())
+                    && self.match_from(token + 1, offset + literal.len())
+            }
+            Some(GlobToken::Any) => {
+                let text = self.text;
+                (offset..=text.len())
+                    .rev()
+                    .filter(|end| text.is_char_boundary(*end))
+                    .any(|end| {
+                        self.spans.push((offset, end));
+                        self.match_from(token + 1, end) || {
+                            self.spans.truncate(len);
+                            false
+                        }
+                    })
+            }
+            Some(single) => match rest.chars().next() {
+                Some(c) if single.matches_char(c) => {
+                    let end = offset + c.len_utf8();
+                    self.spans.push((offset, end));
+                    self.match_from(token + 1, end)
+                }
+                _ => false,
+            },
+        };
+        if !matched {
+            self.spans.truncate(len);
+            self.failed[state] = true;
+        }
+        matched
+    }
+}
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+/// A pattern for a single tier of a level
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum TierPattern {
+    /// Matches a single tier
+    Tier(Glob),
+    /// Matches any number of tiers, including none (`**`)
+    AnyTiers,
+}
+
+// Match tier patterns against the entirety of the tiers, leaving the
+// captures untouched on failure
+fn match_tiers(patterns: &[TierPattern], tiers: &[&str], captures: &mut Vec<String>) -> bool {
+    let (pattern, rest) = match patterns.split_first() {
+        Some(split) => split,
+        None => return tiers.is_empty(),
+    };
+    let len = captures.len();
+    match pattern {
+        TierPattern::Tier(glob) => match tiers.split_first() {
+            Some((tier, remaining)) => {
+                if glob.match_into(tier, captures) && match_tiers(rest, remaining, captures) {
+                    return true;
+                }
+                captures.truncate(len);
+                false
+            }
+            None => false,
+        },
+        TierPattern::AnyTiers => {
+            for count in (0..=tiers.len()).rev() {
+                captures.push(tiers[..count].join("."));
+                if match_tiers(rest, &tiers[count..], captures) {
+                    return true;
+                }
+                captures.truncate(len);
+            }
+            false
+        }
+    }
+}
+
+impl fmt::Display for TierPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tier(glob) => write!(f, "{}", glob),
+            Self::AnyTiers => write!(f, "**"),
+        }
+    }
+}
+
+/// A uri whose components may contain wildcards, used to select asset models
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct AssetPattern {
+    scheme: Glob,
+    level: Vec<TierPattern>,
+    path: Vec<Glob>,
+    query: Vec<(String, Glob)>,
+    key: Option<Glob>,
+    resource_path: Vec<Glob>,
+}
+
+impl AssetPattern {
+    /// constructor function
+    pub fn new(
+        scheme: Glob,
+        level: Vec<TierPattern>,
+        path: Vec<Glob>,
+        query: Vec<(String, Glob)>,
+        key: Option<Glob>,
+        resource_path: Vec<Glob>,
+    ) -> Self {
+        Self {
+            scheme,
+            level,
+            path,
+            query,
+            key,
+            resource_path,
+        }
+    }
+
+    /// Parse a pattern as directed by the supplied config. A scheme without
+    /// wildcards must be registered with the config, and may be given by
+    /// alias unless the config is strict. The path of such a pattern must
+    /// have as many components as the scheme's route
+    pub fn from_str_with_config(pattern: &str, config: &ParseConfig) -> Result<Self, AmuriError> {
+        let invalid = |reason: String| AmuriError::InvalidPattern {
+            pattern: pattern.into(),
+            reason,
+        };
+        let (_, mut parsed) = all_consuming(parse_asset_pattern)(pattern)
+            .map_err(|_| invalid("not a valid pattern".into()))?;
+        if let Some(name) = parsed.scheme.as_literal() {
+            let schemes = config.schemes();
+            let definition = if config.is_strict() {
+                schemes.get(&name)
+            } else {
+                schemes.lookup(&name)
+            }
+            .ok_or_else(|| invalid(format!("scheme '{}' is not registered", name)))?;
+            let layout = definition.layout();
+            let expected = layout.required_len()..=layout.components().len();
+            if !expected.contains(&parsed.path.len()) {
+                return Err(invalid(format!(
+                    "the {} route has {} components, not {}",
+                    definition.name(),
+                    if expected.start() == expected.end() {
+                        expected.end().to_string()
+                    } else {
+                        format!("{} to {}", expected.start(), expected.end())
+                    },
+                    parsed.path.len()
+                )));
+            }
+            parsed.scheme = Glob::new(vec![GlobToken::Literal(definition.name().into())]);
+        }
+        Ok(parsed)
+    }
+
+    /// Test whether the model matches the pattern
+    pub fn matches(&self, model: &AssetModel<'_>) -> bool {
+        self.captures(model).is_some()
+    }
+
+    /// Retrieve the text matched by each wildcard, in pattern order, if the
+    /// model matches the pattern
+    pub fn captures(&self, model: &AssetModel<'_>) -> Option<Vec<String>> {
+        let mut captures = Vec::new();
+        let path = PATH_COMPONENTS
+            .iter()
+            .filter_map(|component| model.component(*component))
+            .collect::<Vec<_>>();
+        let matched = self
+            .scheme
+            .match_into(model.container_type().as_str(), &mut captures)
+            && match_tiers(&self.level, &model.level().tiers(), &mut captures)
+            && self.path.len() == path.len()
+            && self
+                .path
+                .iter()
+                .zip(path)
+                .all(|(glob, value)| glob.match_into(value, &mut captures))
+            && self.query.iter().all(|(key, glob)| {
+                query_value(model, key)
+                    .map_or(false, |value| glob.match_into(&value, &mut captures))
+            })
+            && self.key.as_ref().map_or(true, |glob| {
+                model
+                    .key()
+                    .map_or(false, |key| glob.match_into(key, &mut captures))
+            })
+            && (self.resource_path.is_empty()
+                || self.resource_path.len() == model.resource_path().len()
+                    && self
+                        .resource_path
+                        .iter()
+                        .zip(model.resource_path())
+                        .all(|(glob, segment)| glob.match_into(segment, &mut captures)));
+        if matched {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+}
+
+// the value of a query parameter of the model, including the version and
+// as_of, as it would be written in the uri
+fn query_value(model: &AssetModel<'_>, key: &str) -> Option<String> {
+    match key {
+        "version" => model.version().map(ToString::to_string),
+        "as_of" => model.as_of().map(|as_of| as_of.to_string()),
+        _ => model.query_param(key).map(str::to_string),
+    }
+}
+
+impl FromStr for AssetPattern {
+    type Err = AmuriError;
+
+    /// Parse a pattern, accepting the built-in schemes and their aliases
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Self::from_str_with_config(pattern, &ParseConfig::default())
+    }
+}
+
+impl fmt::Display for AssetPattern {
+    /// Format the pattern as it would be written
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = self
+            .level
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}://{}", self.scheme, level.join("."))?;
+        for glob in &self.path {
+            write!(f, "/{}", glob)?;
+        }
+        let mut separator = '?';
+        for (key, glob) in &self.query {
+            write!(f, "{}{}={}", separator, key, glob)?;
+            separator = '&';
+        }
+        if let Some(key) = &self.key {
+            write!(f, "#{}", key)?;
+            for glob in &self.resource_path {
+                write!(f, "/{}", glob)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::uri::parse_uri;
+
+    fn pattern(input: &str) -> AssetPattern {
+        AssetPattern::from_str(input).unwrap()
+    }
+
+    mod glob {
+        use super::*;
+
+        fn glob(input: &str) -> Glob {
+            match pattern(&format!("asset://dev01/bob/model/hi/{}", input))
+                .path
+                .pop()
+            {
+                Some(glob) => glob,
+                None => panic!("expected a glob"),
+            }
+        }
+
+        #[test]
+        fn can_match_wildcards() {
+            assert!(glob("alembic_*").matches("alembic_model"));
+            assert!(glob("alembic_*").matches("alembic_"));
+            assert!(!glob("alembic_*").matches("maya_model"));
+            assert!(glob("bo?").matches("bob"));
+            assert!(!glob("bo?").matches("bo"));
+            assert!(glob("v[0-9]").matches("v1"));
+            assert!(!glob("v[!0-9]").matches("v1"));
+            assert!(glob("[abc]x").matches("cx"));
+        }
+
+        #[test]
+        fn captures_each_wildcard() {
+            assert_eq!(
+                glob("*_*").captures("alembic_model_hi"),
+                Some(vec!["alembic_model".to_string(), "hi".to_string()])
+            );
+            assert_eq!(
+                glob("v[0-9]?").captures("v12"),
+                Some(vec!["1".to_string(), "2".to_string()])
+            );
+            assert_eq!(glob("maya").captures("maya"), Some(vec![]));
+        }
+
+        #[test]
+        fn does_not_backtrack_exponentially() {
+            let mut tokens = Vec::new();
+            for _ in 0..30 {
+                tokens.push(GlobToken::Any);
+                tokens.push(GlobToken::Literal("a".into()));
+            }
+            tokens.push(GlobToken::Literal("b".into()));
+            let glob = Glob::new(tokens);
+            assert!(!glob.matches(&"a".repeat(200)));
+            assert!(glob.matches(&format!("{}b", "a".repeat(60))));
+        }
+
+        #[test]
+        fn question_mark_before_query_key_begins_query() {
+            let pattern = pattern("asset://dev01/bob/model/hi/alembic_?lod=hi");
+            assert_eq!(pattern.path.last(), Some(&glob("alembic_")));
+            assert_eq!(pattern.query, vec![("lod".to_string(), glob("hi"))]);
+            assert!(glob("alembic_[!/]").matches("alembic_x"));
+        }
+    }
+
+    mod asset_pattern {
+        use super::*;
+
+        const URI: &str = "asset://dev01.rd.0001/bob/model/hi/alembic_model?version=3#main";
+
+        #[test]
+        fn can_match_model() {
+            let model = parse_uri(URI).unwrap();
+            let pattern = pattern("asset://dev01.*.*/*/model/*/alembic_*?version=*");
+            assert!(pattern.matches(&model));
+            assert_eq!(
+                pattern.captures(&model),
+                Some(
+                    vec!["rd", "0001", "bob", "hi", "model", "3"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect()
+                )
+            );
+            assert!(!pattern.matches(&model.with_version(None)));
+            assert!(!pattern.matches(&model.with_department("anim")));
+        }
+
+        #[test]
+        fn any_tiers_matches_any_depth() {
+            let pattern = pattern("asset://dev01.**/bob/model/hi/*");
+            for uri in &[
+                "asset://dev01/bob/model/hi/maya_model",
+                "asset://dev01.rd/bob/model/hi/maya_model",
+                "asset://dev01.rd.0001/bob/model/hi/maya_model",
+            ] {
+                assert!(pattern.matches(&parse_uri(uri).unwrap()), "{}", uri);
+            }
+            assert!(!pattern.matches(&parse_uri("asset://dev02/bob/model/hi/maya_model").unwrap()));
+            assert_eq!(
+                pattern.captures(&parse_uri(URI).unwrap()),
+                Some(vec!["rd.0001".to_string(), "alembic_model".to_string()])
+            );
+        }
+
+        #[test]
+        fn key_and_resource_path_constrain_when_named() {
+            let model =
+                parse_uri("asset://dev01/bob/surface/hi/textures#main/diffuse/color.1001.tx")
+                    .unwrap();
+            assert!(pattern("asset://dev01/bob/surface/hi/textures").matches(&model));
+            assert!(pattern("asset://dev01/bob/surface/hi/textures#m*").matches(&model));
+            assert!(pattern("asset://dev01/bob/surface/hi/textures#main/*/*.tx").matches(&model));
+            assert!(!pattern("asset://dev01/bob/surface/hi/textures#main/*.tx").matches(&model));
+            assert!(!pattern("asset://dev01/bob/surface/hi/textures#lo").matches(&model));
+        }
+
+        #[test]
+        fn wildcard_scheme_matches_any_route() {
+            let pattern = pattern("*://dev01.**/bg*/*");
+            assert!(pattern.matches(&parse_uri("plate://dev01.rd.0001/bg01/exr").unwrap()));
+            assert!(!pattern.matches(&parse_uri(URI).unwrap()));
+        }
+
+        #[test]
+        fn canonicalizes_scheme_aliases() {
+            let pattern = pattern("rnd://dev01/*/*/*/*/*/exr");
+            assert_eq!(pattern.to_string(), "render://dev01/*/*/*/*/*/exr");
+            assert!(pattern
+                .matches(&parse_uri("render://dev01/bob/light/hi/beauty/diffuse/exr").unwrap()));
+        }
+
+        #[test]
+        fn instance_pattern_may_omit_source() {
+            let with_source = parse_uri("instance://dev01/bob1/bob/anim/hi/abc").unwrap();
+            let without_source = parse_uri("instance://dev01/bob1/anim/hi/abc").unwrap();
+            let pattern = pattern("instance://dev01/*/anim/*/*");
+            assert!(pattern.matches(&without_source));
+            assert!(!pattern.matches(&with_source));
+            assert!(self::pattern("instance://dev01/*/*/anim/*/*").matches(&with_source));
+        }
+
+        #[test]
+        fn can_display_pattern() {
+            for input in &[
+                "asset://dev01.**/*/model/[!a-c]?/alembic_*?version=*&lod=hi#main/*.tx",
+                "*://dev01/*/*",
+            ] {
+                assert_eq!(&pattern(input).to_string(), input);
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_patterns() {
+            assert_eq!(
+                AssetPattern::from_str("asset://dev01/*/model/*"),
+                Err(AmuriError::InvalidPattern {
+                    pattern: "asset://dev01/*/model/*".into(),
+                    reason: "the asset route has 4 components, not 3".into()
+                })
+            );
+            assert_eq!(
+                AssetPattern::from_str("instance://dev01/*/*/*"),
+                Err(AmuriError::InvalidPattern {
+                    pattern: "instance://dev01/*/*/*".into(),
+                    reason: "the instance route has 4 to 5 components, not 3".into()
+                })
+            );
+            assert_eq!(
+                AssetPattern::from_str("texture://dev01/*/*/*/*"),
+                Err(AmuriError::InvalidPattern {
+                    pattern: "texture://dev01/*/*/*/*".into(),
+                    reason: "scheme 'texture' is not registered".into()
+                })
+            );
+            assert!(AssetPattern::from_str("asset://dev01/b!b/model/hi/maya").is_err());
+            assert!(AssetPattern::from_str("asset://dev01/[a-/model/hi/maya").is_err());
+            assert_eq!(
+                AssetPattern::from_str("asset://dev01/[z-a]*/model/hi/maya"),
+                Err(AmuriError::InvalidPattern {
+                    pattern: "asset://dev01/[z-a]*/model/hi/maya".into(),
+                    reason: "not a valid pattern".into()
+                })
+            );
+            assert!(AssetPattern::from_str_with_config(
+                "rnd://dev01/*/*/*/*/*/exr",
+                &ParseConfig::new().with_strict(true)
+            )
+            .is_err());
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Route layouts
+//!
+//! A RouteLayout lists the components of the path which follow the level in
+//! a scheme's uri, such as name/department/subcontext/snapshot_type. Each
+//! scheme has its own layout:
+//! - asset: name/department/subcontext/snapshot_type
+//! - instance: name/[source]/department/subcontext/snapshot_type, where the
+//!   optional source is the name of the asset from which the instance derives
+//! - render: name/department/subcontext/layer/pass/snapshot_type
+//! - plate: name/snapshot_type
+//!
+//! Every layout starts with the name and ends with the snapshot type, and
+//! its components appear in a fixed relative order (name, source,
+//! department, subcontext, layer, pass, snapshot type), so that a model may
+//! be written out without consulting its layout.
+//!
+//! Components other than the name and snapshot type may be optional. When a
+//! uri has fewer components than its layout, the optional components are
+//! omitted, starting with the last.
+use crate::errors::{AmuriError, UriComponent};
+use std::fmt;
+
+/// The components which may appear in the path of a route, in the order in
+/// which they must appear
+pub const PATH_COMPONENTS: [UriComponent; 7] = [
+    UriComponent::Name,
+    UriComponent::Source,
+    UriComponent::Department,
+    UriComponent::Subcontext,
+    UriComponent::Layer,
+    UriComponent::Pass,
+    UriComponent::SnapshotType,
+];
+
+/// The ordered components of the path of a route, and which of them are
+/// optional
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RouteLayout {
+    components: Vec<UriComponent>,
+    optional: Vec<UriComponent>,
+}
+
+impl Default for RouteLayout {
+    /// The default layout: name, department, subcontext and snapshot type
+    fn default() -> Self {
+        Self {
+            components: vec![
+                UriComponent::Name,
+                UriComponent::Department,
+                UriComponent::Subcontext,
+                UriComponent::SnapshotType,
+            ],
+            optional: Vec::new(),
+        }
+    }
+}
+
+impl RouteLayout {
+    /// constructor function. The layout must start with the name, end with
+    /// the snapshot type, and list only path components, each at most once
+    /// and in the order given by `PATH_COMPONENTS`
+    pub fn new<I>(components: I) -> Result<Self, AmuriError>
+    where
+        I: IntoIterator<Item = UriComponent>,
+    {
+        let components = components.into_iter().collect::<Vec<_>>();
+        if components.first() != Some(&UriComponent::Name) {
+            return Err(AmuriError::InvalidRouteLayout(
+                "a layout must start with the name".into(),
+            ));
+        }
+        if components.last() != Some(&UriComponent::SnapshotType) {
+            return Err(AmuriError::InvalidRouteLayout(
+                "a layout must end with the snapshot type".into(),
+            ));
+        }
+        let mut last = None;
+        for component in &components {
+            let position = PATH_COMPONENTS
+                .iter()
+                .position(|c| c == component)
+                .ok_or_else(|| {
+                    AmuriError::InvalidRouteLayout(format!(
+                        "{} is not a component of the path",
+                        component
+                    ))
+                })?;
+            if last.map_or(false, |last| position <= last) {
+                return Err(AmuriError::InvalidRouteLayout(format!(
+                    "{} is repeated or out of order",
+                    component
+                )));
+            }
+            last = Some(position);
+        }
+        Ok(Self {
+            components,
+            optional: Vec::new(),
+        })
+    }
+
+    /// Make a component of the layout optional. The name and snapshot type
+    /// may not be optional
+    pub fn with_optional(mut self, component: UriComponent) -> Result<Self, AmuriError> {
+        if !self.contains(component) {
+            return Err(AmuriError::InvalidRouteLayout(format!(
+                "{} is not part of the layout",
+                component
+            )));
+        }
+        if component == UriComponent::Name || component == UriComponent::SnapshotType {
+            return Err(AmuriError::InvalidRouteLayout(format!(
+                "the {} may not be optional",
+                component
+            )));
+        }
+        if !self.optional.contains(&component) {
+            self.optional.push(component);
+        }
+        Ok(self)
+    }
+
+    /// Retrieve the components, in order
+    pub fn components(&self) -> &[UriComponent] {
+        &self.components
+    }
+
+    /// Test whether the layout includes the component
+    pub fn contains(&self, component: UriComponent) -> bool {
+        self.components.contains(&component)
+    }
+
+    /// Test whether the component is an optional part of the layout
+    pub fn is_optional(&self, component: UriComponent) -> bool {
+        self.optional.contains(&component)
+    }
+
+    /// Test whether every route of the layout has the component
+    pub fn is_required(&self, component: UriComponent) -> bool {
+        self.contains(component) && !self.is_optional(component)
+    }
+
+    /// The number of components which every route of the layout has
+    pub fn required_len(&self) -> usize {
+        self.components.len() - self.optional.len()
+    }
+}
+
+impl fmt::Display for RouteLayout {
+    /// Format the layout as a path of component names, with optional
+    /// components in brackets (eg name/[source]/department/subcontext/snapshot_type)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self
+            .components
+            .iter()
+            .map(|c| {
+                let name = c.to_string().replace(' ', "_");
+                if self.is_optional(*c) {
+                    format!("[{}]", name)
+                } else {
+                    name
+                }
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", names.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use UriComponent::*;
+
+    #[test]
+    fn default_layout_is_name_department_subcontext_snapshot_type() {
+        assert_eq!(
+            RouteLayout::default().to_string(),
+            "name/department/subcontext/snapshot_type"
+        );
+        assert!(!RouteLayout::default().contains(Layer));
+    }
+
+    #[test]
+    fn can_construct_layout() {
+        let layout = RouteLayout::new(vec![Name, SnapshotType]).unwrap();
+        assert_eq!(layout.components(), &[Name, SnapshotType]);
+    }
+
+    #[test]
+    fn rejects_invalid_layout() {
+        assert_eq!(
+            RouteLayout::new(vec![Department, Name, SnapshotType]),
+            Err(AmuriError::InvalidRouteLayout(
+                "a layout must start with the name".into()
+            ))
+        );
+        assert_eq!(
+            RouteLayout::new(vec![Name, Department]),
+            Err(AmuriError::InvalidRouteLayout(
+                "a layout must end with the snapshot type".into()
+            ))
+        );
+        assert_eq!(
+            RouteLayout::new(vec![Name, Pass, Layer, SnapshotType]),
+            Err(AmuriError::InvalidRouteLayout(
+                "layer is repeated or out of order".into()
+            ))
+        );
+        assert!(RouteLayout::new(vec![Name, Key, SnapshotType]).is_err());
+        assert!(RouteLayout::new(vec![Name, Layer, Layer, SnapshotType]).is_err());
+    }
+
+    #[test]
+    fn can_make_components_optional() {
+        let layout = RouteLayout::default().with_optional(Subcontext).unwrap();
+        assert!(layout.is_optional(Subcontext));
+        assert!(layout.is_required(Department));
+        assert!(!layout.is_required(Layer));
+        assert_eq!(layout.required_len(), 3);
+        assert_eq!(
+            layout.to_string(),
+            "name/department/[subcontext]/snapshot_type"
+        );
+        assert_eq!(
+            RouteLayout::default().with_optional(Layer),
+            Err(AmuriError::InvalidRouteLayout(
+                "layer is not part of the layout".into()
+            ))
+        );
+        assert_eq!(
+            RouteLayout::default().with_optional(SnapshotType),
+            Err(AmuriError::InvalidRouteLayout(
+                "the snapshot type may not be optional".into()
+            ))
+        );
+    }
+}
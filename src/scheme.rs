@@ -1,19 +1,169 @@
-use strum_macros::{AsRefStr, Display, EnumIter, EnumString, IntoStaticStr};
+use crate::errors::{AmuriError, UriComponent};
+use crate::parse::container_name::NameRule;
+use crate::route::RouteLayout;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, EnumString};
 
-#[derive(Debug, Display, PartialEq, Eq, AsRefStr, EnumString, EnumIter, IntoStaticStr, Clone)]
+/// The scheme of a uri. Schemes are ordered by declaration, with custom
+/// schemes last, in alphabetical order.
+///
+/// The four built-in schemes may be parsed via `FromStr`. Custom schemes
+/// are constructed via `Scheme::named`, and must be registered with a
+/// `SchemeRegistry` to be parsed.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, EnumIter, Clone)]
 #[strum(serialize_all = "snake_case")]
 pub enum Scheme {
     Asset,
     Instance,
     Render,
     Plate,
+    /// A scheme registered at runtime (eg texture)
+    #[strum(disabled = "true")]
+    Custom(CustomName),
+}
+
+/// The name of a custom scheme. Custom names are interned, so that, like
+/// the names of the built-in schemes, they may be borrowed for the life of
+/// the program. Each distinct name is allocated once.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct CustomName(&'static str);
+
+impl CustomName {
+    // intern the name, reusing the allocation of an earlier equal name
+    fn intern(name: String) -> Self {
+        // kept sorted, so that names may be found by binary search
+        static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+        match names.binary_search(&name.as_str()) {
+            Ok(index) => Self(names[index]),
+            Err(index) => {
+                let interned: &'static str = Box::leak(name.into_boxed_str());
+                names.insert(index, interned);
+                Self(interned)
+            }
+        }
+    }
+
+    /// Retrieve the name
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl Scheme {
+    /// Construct the scheme with the supplied name. This is the built-in
+    /// scheme of that name, if there is one, and a custom scheme otherwise
+    pub fn named<I: Into<String>>(name: I) -> Self {
+        let name = name.into();
+        Self::from_str(&name).unwrap_or_else(|_| Self::Custom(CustomName::intern(name)))
+    }
+
+    /// Iterate over the built-in schemes
+    pub fn builtins() -> impl Iterator<Item = Scheme> {
+        Self::iter()
+    }
+
+    /// Retrieve the name of the scheme (eg asset)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Asset => "asset",
+            Self::Instance => "instance",
+            Self::Render => "render",
+            Self::Plate => "plate",
+            Self::Custom(name) => name.as_str(),
+        }
+    }
+
+    /// Retrieve the default rule which container names must follow for the
+    /// scheme. Asset and instance names follow the rules of
+    /// `parse::container_name`; render, plate and custom schemes accept any
+    /// name by default. A `SchemeRegistry` may assign a different rule to any
+    /// scheme
+    pub fn name_rule(&self) -> NameRule {
+        match self {
+            Self::Asset => NameRule::NoTrailingDigit,
+            Self::Instance => NameRule::TrailingDigit,
+            Self::Render | Self::Plate | Self::Custom(_) => NameRule::Any,
+        }
+    }
+
+    /// Retrieve the default layout of the path of the scheme's route. The
+    /// source of an instance is optional, so that instances written without
+    /// one follow the default layout. Custom schemes use the default layout;
+    /// a `SchemeRegistry` may assign a different layout to any scheme
+    pub fn route_layout(&self) -> RouteLayout {
+        use UriComponent::*;
+        // should be able to unwrap here because the built-in layouts are in
+        // the prescribed order
+        match self {
+            Self::Asset | Self::Custom(_) => RouteLayout::default(),
+            Self::Instance => {
+                RouteLayout::new(vec![Name, Source, Department, Subcontext, SnapshotType])
+                    .and_then(|layout| layout.with_optional(Source))
+                    .unwrap()
+            }
+            Self::Render => RouteLayout::new(vec![
+                Name,
+                Department,
+                Subcontext,
+                Layer,
+                Pass,
+                SnapshotType,
+            ])
+            .unwrap(),
+            Self::Plate => RouteLayout::new(vec![Name, SnapshotType]).unwrap(),
+        }
+    }
+
+    /// Verify that the supplied container name satisfies the scheme's name rule
+    pub fn validate_name(&self, name: &str) -> Result<(), AmuriError> {
+        self.validate_name_with(self.name_rule(), name)
+    }
+
+    /// Verify that the supplied container name satisfies the supplied rule
+    pub(crate) fn validate_name_with(&self, rule: NameRule, name: &str) -> Result<(), AmuriError> {
+        if rule.matches(name) {
+            Ok(())
+        } else {
+            Err(AmuriError::NameRuleViolation {
+                scheme: self.clone(),
+                name: name.into(),
+                rule: rule.description(),
+            })
+        }
+    }
+}
+
+impl AsRef<str> for Scheme {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<Scheme> for &'static str {
+    fn from(scheme: Scheme) -> Self {
+        scheme.as_str()
+    }
+}
+
+impl<'a> From<&'a Scheme> for &'static str {
+    fn from(scheme: &'a Scheme) -> Self {
+        scheme.as_str()
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::str::FromStr;
-    use strum::IntoEnumIterator;
 
     #[test]
     fn test_can_display() {
@@ -31,11 +181,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_can_convert_to_static_str() {
+        let tests = ["asset", "instance", "render", "plate"];
+        for (cnt, scheme) in Scheme::iter().enumerate() {
+            let name: &'static str = (&scheme).into();
+            assert_eq!(name, tests[cnt]);
+            let name: &'static str = scheme.into();
+            assert_eq!(name, tests[cnt]);
+        }
+        let name: &'static str = Scheme::named("texture").into();
+        assert_eq!(name, "texture");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(Scheme::Asset.validate_name("bob"), Ok(()));
+        assert_eq!(Scheme::Instance.validate_name("bob1"), Ok(()));
+        assert_eq!(Scheme::Render.validate_name("beauty"), Ok(()));
+        assert_eq!(Scheme::Plate.validate_name("bg01"), Ok(()));
+        assert_eq!(
+            Scheme::Asset.validate_name("bob1"),
+            Err(AmuriError::NameRuleViolation {
+                scheme: Scheme::Asset,
+                name: "bob1".into(),
+                rule: "name must not end with a digit"
+            })
+        );
+        assert!(Scheme::Instance.validate_name("bob").is_err());
+        assert_eq!(Scheme::Plate.validate_name("bg"), Ok(()));
+    }
+
     #[test]
     fn test_create_from_str() {
         let tests = ["asset", "instance", "render", "plate"];
         for cnt in 0..tests.len() {
             assert_eq!(Scheme::from_str(tests[cnt]).is_ok(), true);
         }
+        assert!(Scheme::from_str("texture").is_err());
+    }
+
+    #[test]
+    fn test_route_layout() {
+        assert_eq!(
+            Scheme::Plate.route_layout().to_string(),
+            "name/snapshot_type"
+        );
+        assert_eq!(
+            Scheme::Render.route_layout().to_string(),
+            "name/department/subcontext/layer/pass/snapshot_type"
+        );
+        assert_eq!(
+            Scheme::Instance.route_layout().to_string(),
+            "name/[source]/department/subcontext/snapshot_type"
+        );
+        assert_eq!(
+            Scheme::named("texture").route_layout(),
+            RouteLayout::default()
+        );
+    }
+
+    #[test]
+    fn test_named() {
+        assert_eq!(Scheme::named("asset"), Scheme::Asset);
+        assert!(
+            matches!(Scheme::named("texture"), Scheme::Custom(name) if name.as_str() == "texture")
+        );
+        assert_eq!(
+            Scheme::named("texture"),
+            Scheme::named(String::from("texture"))
+        );
+        assert_eq!(Scheme::named("texture").to_string(), "texture");
+        assert_eq!(Scheme::builtins().count(), 4);
+        assert!(Scheme::Plate < Scheme::named("camera"));
+        assert!(Scheme::named("camera") < Scheme::named("texture"));
     }
 }
@@ -0,0 +1,455 @@
+//! The schemes known to the parser
+//!
+//! A SchemeRegistry holds a SchemeDefinition for each scheme which may appear
+//! in a uri. Each definition names the scheme, the rule which its container
+//! names must follow, the layout of the path of its route, and the optional
+//! components of the uri which the scheme requires (eg a texture scheme
+//! might require a key). The four built-in schemes are registered by
+//! default, and further schemes may be registered at runtime.
+//!
+//! The registry also holds a table of aliases, which are shorthand names for
+//! registered schemes (eg inst for instance). By default, inst and rnd are
+//! aliases of instance and render. A model parsed from an alias always
+//! reports the canonical scheme.
+use crate::assetmodel::AssetModel;
+use crate::errors::{AmuriError, UriComponent};
+use crate::parse::{container_name::NameRule, resource::parse_resource};
+use crate::route::RouteLayout;
+use crate::scheme::Scheme;
+use nom::combinator::all_consuming;
+use std::collections::BTreeMap;
+
+const SCHEME_NAME_RULE: &str =
+    "must start with a letter, contain only letters, digits and underscores, and not end with an underscore";
+
+// Verify that the name of a scheme or alias may appear in a uri
+fn check_scheme_name(name: String) -> Result<String, AmuriError> {
+    if all_consuming(parse_resource)(name.as_str()).is_err() {
+        return Err(AmuriError::InvalidComponent {
+            component: UriComponent::Scheme,
+            value: name,
+            reason: SCHEME_NAME_RULE.into(),
+        });
+    }
+    Ok(name)
+}
+
+/// The name rule, route layout and required components of a scheme
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SchemeDefinition {
+    scheme: Scheme,
+    name_rule: NameRule,
+    layout: RouteLayout,
+    required: Vec<UriComponent>,
+}
+
+impl SchemeDefinition {
+    /// constructor function. The definition adopts the default name rule and
+    /// route layout of the scheme, and requires none of the optional
+    /// components
+    pub fn new<I: Into<String>>(name: I) -> Result<Self, AmuriError> {
+        let name = check_scheme_name(name.into())?;
+        Ok(Self::from(Scheme::named(name)))
+    }
+
+    /// Set the rule which container names must follow
+    pub fn with_name_rule(mut self, name_rule: NameRule) -> Self {
+        self.name_rule = name_rule;
+        self
+    }
+
+    /// Set the layout of the path of the scheme's route
+    pub fn with_layout(mut self, layout: RouteLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Require an optional component of the uri (the version, query, key or
+    /// resource path). The version is satisfied by a version query parameter,
+    /// and the query by any query parameter
+    pub fn with_required(mut self, component: UriComponent) -> Self {
+        if !self.required.contains(&component) {
+            self.required.push(component);
+        }
+        self
+    }
+
+    /// Retrieve the scheme
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
+    /// Retrieve the name of the scheme
+    pub fn name(&self) -> &str {
+        self.scheme.as_str()
+    }
+
+    /// Retrieve the rule which container names must follow
+    pub fn name_rule(&self) -> NameRule {
+        self.name_rule
+    }
+
+    /// Retrieve the layout of the path of the scheme's route
+    pub fn layout(&self) -> &RouteLayout {
+        &self.layout
+    }
+
+    /// Retrieve the components which the scheme requires, in the order in
+    /// which they were required
+    pub fn required(&self) -> &[UriComponent] {
+        &self.required
+    }
+
+    /// Verify that the container name satisfies the scheme's name rule
+    pub fn validate_name(&self, name: &str) -> Result<(), AmuriError> {
+        self.scheme.validate_name_with(self.name_rule, name)
+    }
+
+    /// Verify that the model supplies each component which the scheme
+    /// requires, reporting the first which is missing
+    pub fn check_required(&self, model: &AssetModel<'_>) -> Result<(), AmuriError> {
+        for component in &self.required {
+            let present = match component {
+                UriComponent::Version => model.version().is_some(),
+                UriComponent::AsOf => model.as_of().is_some(),
+                UriComponent::Query => {
+                    model.version().is_some()
+                        || model.as_of().is_some()
+                        || model.query_params().next().is_some()
+                }
+                UriComponent::Key => model.key().is_some(),
+                UriComponent::ResourcePath => !model.resource_path().is_empty(),
+                _ => true,
+            };
+            if !present {
+                return Err(AmuriError::MissingComponent(*component));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Scheme> for SchemeDefinition {
+    fn from(scheme: Scheme) -> Self {
+        Self {
+            name_rule: scheme.name_rule(),
+            layout: scheme.route_layout(),
+            scheme,
+            required: Vec::new(),
+        }
+    }
+}
+
+/// The schemes which may appear in a uri, keyed by name, along with their
+/// aliases
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SchemeRegistry {
+    schemes: BTreeMap<String, SchemeDefinition>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl Default for SchemeRegistry {
+    /// The registry of the built-in schemes: asset, instance, render and
+    /// plate, with the aliases inst and rnd
+    fn default() -> Self {
+        Self {
+            schemes: Scheme::builtins()
+                .map(|scheme| (scheme.to_string(), SchemeDefinition::from(scheme)))
+                .collect(),
+            aliases: vec![("inst", Scheme::Instance), ("rnd", Scheme::Render)]
+                .into_iter()
+                .map(|(alias, scheme)| (alias.to_string(), scheme.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl SchemeRegistry {
+    /// constructor function. The built-in schemes are registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a scheme, replacing any existing definition of the same
+    /// name. Returns an error if the name is already an alias
+    pub fn with_scheme(mut self, definition: SchemeDefinition) -> Result<Self, AmuriError> {
+        if self.aliases.contains_key(definition.name()) {
+            return Err(AmuriError::AliasConflict(definition.name().to_string()));
+        }
+        self.schemes
+            .insert(definition.name().to_string(), definition);
+        Ok(self)
+    }
+
+    /// Register an alias for a scheme, replacing any existing alias of the
+    /// same name. The alias must follow the same rule as a scheme name, must
+    /// not be the name of a registered scheme, and the scheme must already
+    /// be registered
+    pub fn with_alias<I: Into<String>>(
+        mut self,
+        alias: I,
+        scheme: &Scheme,
+    ) -> Result<Self, AmuriError> {
+        let alias = check_scheme_name(alias.into())?;
+        if self.get(&alias).is_some() {
+            return Err(AmuriError::AliasConflict(alias));
+        }
+        if self.definition(scheme).is_none() {
+            return Err(AmuriError::SchemeNotRegistered(scheme.clone()));
+        }
+        self.aliases.insert(alias, scheme.to_string());
+        Ok(self)
+    }
+
+    /// Remove every alias, so that schemes are known only by their canonical
+    /// names
+    pub fn without_aliases(mut self) -> Self {
+        self.aliases.clear();
+        self
+    }
+
+    /// Retrieve the definition of the scheme with the supplied canonical
+    /// name
+    pub fn get(&self, name: &str) -> Option<&SchemeDefinition> {
+        self.schemes.get(name)
+    }
+
+    /// Retrieve the definition of the scheme with the supplied canonical
+    /// name or alias
+    pub fn lookup(&self, name: &str) -> Option<&SchemeDefinition> {
+        self.get(name).or_else(|| {
+            self.aliases
+                .get(name)
+                .and_then(|canonical| self.get(canonical))
+        })
+    }
+
+    /// Test whether the supplied name is an alias, rather than the canonical
+    /// name, of a registered scheme
+    pub fn is_alias(&self, name: &str) -> bool {
+        self.get(name).is_none() && self.lookup(name).is_some()
+    }
+
+    /// Iterate over the aliases of registered schemes along with the
+    /// canonical names of the schemes, in alphabetical order of alias
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .filter(move |(alias, _)| self.is_alias(alias))
+            .map(|(alias, canonical)| (alias.as_str(), canonical.as_str()))
+    }
+
+    /// Retrieve the definition of the scheme
+    pub fn definition(&self, scheme: &Scheme) -> Option<&SchemeDefinition> {
+        self.get(scheme.as_str())
+    }
+
+    /// Iterate over the names of the registered schemes, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.schemes.keys().map(String::as_str)
+    }
+
+    /// Verify that the container name satisfies the name rule of the scheme.
+    /// Unregistered schemes are held to their default name rule
+    pub fn validate_name(&self, scheme: &Scheme, name: &str) -> Result<(), AmuriError> {
+        match self.definition(scheme) {
+            Some(definition) => definition.validate_name(name),
+            None => scheme.validate_name(name),
+        }
+    }
+
+    /// Retrieve the rule which the scheme's container names must follow.
+    /// Unregistered schemes use their default rule
+    pub fn name_rule(&self, scheme: &Scheme) -> NameRule {
+        self.definition(scheme)
+            .map_or_else(|| scheme.name_rule(), SchemeDefinition::name_rule)
+    }
+
+    /// Retrieve the layout of the path of the scheme's route. Unregistered
+    /// schemes use their default layout
+    pub fn layout(&self, scheme: &Scheme) -> RouteLayout {
+        self.definition(scheme).map_or_else(
+            || scheme.route_layout(),
+            |definition| definition.layout().clone(),
+        )
+    }
+
+    /// Verify that the model supplies each component which its scheme
+    /// requires. Unregistered schemes require nothing
+    pub fn check_required(&self, model: &AssetModel<'_>) -> Result<(), AmuriError> {
+        self.definition(model.container_type())
+            .map_or(Ok(()), |definition| definition.check_required(model))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::uri::parse_uri;
+
+    fn texture() -> SchemeDefinition {
+        SchemeDefinition::new("texture")
+            .unwrap()
+            .with_name_rule(NameRule::NoTrailingDigit)
+            .with_required(UriComponent::Key)
+    }
+
+    mod scheme_definition {
+        use super::*;
+
+        #[test]
+        fn rejects_invalid_name() {
+            assert!(SchemeDefinition::new("1texture").is_err());
+            assert!(SchemeDefinition::new("tex-ture").is_err());
+        }
+
+        #[test]
+        fn builtin_name_yields_builtin_scheme() {
+            let definition = SchemeDefinition::new("plate").unwrap();
+            assert_eq!(definition.scheme(), &Scheme::Plate);
+            assert_eq!(definition.name_rule(), NameRule::Any);
+        }
+
+        #[test]
+        fn can_check_required_components() {
+            let model = parse_uri("asset://dev01/bob/model/hi/maya_model").unwrap();
+            let definition = SchemeDefinition::from(Scheme::Asset)
+                .with_required(UriComponent::Version)
+                .with_required(UriComponent::Key);
+            assert_eq!(
+                definition.check_required(&model),
+                Err(AmuriError::MissingComponent(UriComponent::Version))
+            );
+            let model = model.with_version(Some(crate::version::Version::Current));
+            assert_eq!(
+                definition.check_required(&model),
+                Err(AmuriError::MissingComponent(UriComponent::Key))
+            );
+            assert_eq!(
+                definition.check_required(&model.with_key(Some("main"))),
+                Ok(())
+            );
+        }
+    }
+
+    mod scheme_registry {
+        use super::*;
+
+        #[test]
+        fn builtins_are_registered_by_default() {
+            let registry = SchemeRegistry::new();
+            assert_eq!(
+                registry.names().collect::<Vec<_>>(),
+                vec!["asset", "instance", "plate", "render"]
+            );
+            assert!(registry.get("texture").is_none());
+        }
+
+        #[test]
+        fn can_register_scheme() {
+            let registry = SchemeRegistry::new().with_scheme(texture()).unwrap();
+            assert_eq!(
+                registry.get("texture").map(SchemeDefinition::scheme),
+                Some(&Scheme::named("texture"))
+            );
+            assert_eq!(
+                registry.validate_name(&Scheme::named("texture"), "bob1"),
+                Err(AmuriError::NameRuleViolation {
+                    scheme: Scheme::named("texture"),
+                    name: "bob1".into(),
+                    rule: "name must not end with a digit"
+                })
+            );
+        }
+
+        #[test]
+        fn can_lookup_aliases() {
+            let registry = SchemeRegistry::new()
+                .with_scheme(texture())
+                .unwrap()
+                .with_alias("tex", &Scheme::named("texture"))
+                .unwrap();
+            assert_eq!(
+                registry.lookup("inst").map(SchemeDefinition::scheme),
+                Some(&Scheme::Instance)
+            );
+            assert_eq!(
+                registry.lookup("tex").map(SchemeDefinition::scheme),
+                Some(&Scheme::named("texture"))
+            );
+            assert!(registry.get("inst").is_none());
+            assert!(registry.is_alias("rnd"));
+            assert!(!registry.is_alias("render"));
+            assert_eq!(
+                registry.aliases().collect::<Vec<_>>(),
+                vec![("inst", "instance"), ("rnd", "render"), ("tex", "texture")]
+            );
+            assert_eq!(registry.without_aliases().aliases().count(), 0);
+        }
+
+        #[test]
+        fn rejects_invalid_aliases() {
+            assert_eq!(
+                SchemeRegistry::new().with_alias("cam", &Scheme::named("camera")),
+                Err(AmuriError::SchemeNotRegistered(Scheme::named("camera")))
+            );
+            assert_eq!(
+                SchemeRegistry::new().with_alias("in st", &Scheme::Instance),
+                Err(AmuriError::InvalidComponent {
+                    component: UriComponent::Scheme,
+                    value: "in st".into(),
+                    reason: SCHEME_NAME_RULE.into(),
+                })
+            );
+            assert!(SchemeRegistry::new()
+                .with_alias("1nst", &Scheme::Instance)
+                .is_err());
+        }
+
+        #[test]
+        fn rejects_names_shared_by_schemes_and_aliases() {
+            assert_eq!(
+                SchemeRegistry::new().with_alias("plate", &Scheme::Asset),
+                Err(AmuriError::AliasConflict("plate".into()))
+            );
+            let registry = SchemeRegistry::new()
+                .with_alias("tex", &Scheme::Asset)
+                .unwrap();
+            assert_eq!(
+                registry.with_scheme(SchemeDefinition::new("tex").unwrap()),
+                Err(AmuriError::AliasConflict("tex".into()))
+            );
+        }
+
+        #[test]
+        fn can_register_layout() {
+            let layout = RouteLayout::new(vec![
+                UriComponent::Name,
+                UriComponent::Layer,
+                UriComponent::SnapshotType,
+            ])
+            .unwrap();
+            let registry = SchemeRegistry::new()
+                .with_scheme(texture().with_layout(layout.clone()))
+                .unwrap();
+            assert_eq!(registry.layout(&Scheme::named("texture")), layout);
+            assert_eq!(
+                registry.layout(&Scheme::Plate),
+                Scheme::Plate.route_layout()
+            );
+            assert_eq!(
+                registry.layout(&Scheme::named("camera")),
+                RouteLayout::default()
+            );
+        }
+
+        #[test]
+        fn registering_builtin_replaces_its_definition() {
+            let registry = SchemeRegistry::new()
+                .with_scheme(SchemeDefinition::from(Scheme::Asset).with_name_rule(NameRule::Any))
+                .unwrap();
+            assert_eq!(registry.validate_name(&Scheme::Asset, "bob1"), Ok(()));
+            assert_eq!(registry.names().count(), 4);
+        }
+    }
+}
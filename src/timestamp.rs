@@ -0,0 +1,207 @@
+//! Points in time, used to resolve versions as they were at a given moment
+//!
+//! A Timestamp is a number of seconds since the unix epoch, in UTC. It may
+//! be written either as that number of seconds (eg 1583020800) or as an
+//! ISO 8601 date (eg 2020-03-01) or date and time (eg 2020-03-01T12:30:00Z).
+use crate::errors::AmuriError;
+use crate::parse::timestamp::parse_timestamp;
+use nom::combinator::all_consuming;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 86_400;
+// days between 0000-03-01 and 1970-01-01 in the proleptic gregorian calendar
+const EPOCH_DAYS: u64 = 719_468;
+const DAYS_PER_ERA: u64 = 146_097;
+
+/// Seconds since the unix epoch, in UTC
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    /// constructor function
+    pub fn from_secs(secs: u64) -> Self {
+        Self(secs)
+    }
+
+    /// Construct a Timestamp from a UTC date and time. Returns None if the
+    /// date or time is invalid, or precedes the unix epoch
+    pub fn from_ymd_hms(
+        year: u32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<Self> {
+        if year < 1970
+            || day == 0
+            || day > days_in_month(year, month)?
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        let days = days_from_civil(year.into(), month.into(), day.into());
+        let secs = u64::from(hour * 3600 + minute * 60 + second);
+        days.checked_mul(SECS_PER_DAY)?.checked_add(secs).map(Self)
+    }
+
+    /// The current time
+    pub fn now() -> Self {
+        Self(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        )
+    }
+
+    /// Retrieve the number of seconds since the unix epoch
+    pub fn secs(&self) -> u64 {
+        self.0
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u32, month: u32) -> Option<u32> {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if is_leap_year(year) => Some(29),
+        2 => Some(28),
+        _ => None,
+    }
+}
+
+// Days since the epoch of a date no earlier than the epoch. The year is
+// shifted to start in March, so that the leap day falls at its end.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * DAYS_PER_ERA + day_of_era - EPOCH_DAYS
+}
+
+// The (year, month, day) of a number of days since the epoch
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + EPOCH_DAYS;
+    let era = days / DAYS_PER_ERA;
+    let day_of_era = days - era * DAYS_PER_ERA;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+impl FromStr for Timestamp {
+    type Err = AmuriError;
+
+    /// Parse a Timestamp from seconds since the epoch (eg 1583020800), a
+    /// date (eg 2020-03-01) or a date and time (eg 2020-03-01T12:30:00Z)
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        all_consuming(parse_timestamp)(input)
+            .map(|(_, timestamp)| timestamp)
+            .map_err(|_| AmuriError::TimestampParsingFailure(input.into()))
+    }
+}
+
+impl TryFrom<&str> for Timestamp {
+    type Error = AmuriError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::from_str(input)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// Format the Timestamp as an ISO 8601 date and time (eg 2020-03-01T12:30:00Z)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.0 / SECS_PER_DAY);
+        let secs = self.0 % SECS_PER_DAY;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_construct_from_date_and_time() {
+        assert_eq!(
+            Timestamp::from_ymd_hms(1970, 1, 1, 0, 0, 0),
+            Some(Timestamp::from_secs(0))
+        );
+        assert_eq!(
+            Timestamp::from_ymd_hms(2020, 3, 1, 12, 30, 0),
+            Some(Timestamp::from_secs(1_583_065_800))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(Timestamp::from_ymd_hms(2019, 2, 29, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_ymd_hms(2020, 13, 1, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_ymd_hms(2020, 1, 1, 24, 0, 0), None);
+        assert_eq!(Timestamp::from_ymd_hms(1969, 12, 31, 0, 0, 0), None);
+        assert!(Timestamp::from_ymd_hms(2020, 2, 29, 0, 0, 0).is_some());
+    }
+
+    #[test]
+    fn can_parse_each_form() {
+        let expected = Ok(Timestamp::from_secs(1_583_020_800));
+        assert_eq!(Timestamp::from_str("1583020800"), expected);
+        assert_eq!(Timestamp::from_str("2020-03-01"), expected);
+        assert_eq!(Timestamp::from_str("2020-03-01T00:00:00Z"), expected);
+        assert_eq!(Timestamp::from_str("2020-03-01T00:00:00"), expected);
+    }
+
+    #[test]
+    fn will_return_error_if_given_invalid_str() {
+        assert_eq!(
+            Timestamp::from_str("2020-02-30"),
+            Err(AmuriError::TimestampParsingFailure("2020-02-30".into()))
+        );
+        assert!(Timestamp::from_str("yesterday").is_err());
+        assert!(Timestamp::from_str("2020-03-01T12:30").is_err());
+    }
+
+    #[test]
+    fn displays_as_iso_8601() {
+        assert_eq!(
+            Timestamp::from_secs(1_583_065_800).to_string(),
+            "2020-03-01T12:30:00Z"
+        );
+        assert_eq!(Timestamp::from_secs(0).to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            Timestamp::from_secs(951_868_799).to_string(),
+            "2000-02-29T23:59:59Z"
+        );
+    }
+}
@@ -1,23 +1,357 @@
 use crate::errors::AmuriError;
-#[derive(Debug, PartialEq, Eq, Clone)]
+use crate::history::{PublishHistory, TagsAsOf};
+use crate::parse::resource::parse_resource;
+use crate::parse::version::parse_relative_version;
+use crate::timestamp::Timestamp;
+use crate::version_req::VersionReq;
+use nom::combinator::all_consuming;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// The version of a snapshot.
+///
+/// Versions are ordered with `Current` first, then `Latest`, followed by
+/// numbered versions in numeric order (see `VersionNumber`), then constraints, then relative
+/// selectors, and finally tags in alphabetical order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum Version {
     Current,
     Latest,
-    Number(u16),
+    /// A version number. The payload is a `VersionNumber` rather than a bare
+    /// integer so that zero padding survives display; numbers written with
+    /// different padding (eg 3 and 0003) are different versions of the uri,
+    /// although they resolve to the same snapshot
+    Number(VersionNumber),
+    /// A constraint, such as >=3,<7, matched against available versions
+    Req(VersionReq),
+    /// An offset, counted in available versions, from the current or latest
+    /// version (eg latest-1)
+    Relative {
+        anchor: Anchor,
+        offset: i32,
+    },
+    /// A named tag, such as approved, bound to a specific version number
+    Tag(String),
+}
+
+/// Looks up the version number to which a tag is bound
+pub trait TagResolver {
+    /// Retrieve the version number bound to the tag, if any
+    fn resolve_tag(&self, tag: &str) -> Option<u32>;
+}
+
+impl TagResolver for HashMap<String, u32> {
+    fn resolve_tag(&self, tag: &str) -> Option<u32> {
+        self.get(tag).copied()
+    }
+}
+
+impl TagResolver for BTreeMap<String, u32> {
+    fn resolve_tag(&self, tag: &str) -> Option<u32> {
+        self.get(tag).copied()
+    }
+}
+
+/// A version number, which remembers the number of digits it was written
+/// with so that zero padding (eg 0003) is reproduced when it is displayed.
+///
+/// A width which would not pad the number is discarded on construction, so
+/// numbers are equal exactly when they display the same. Numbers are ordered
+/// by value, then by width; 3 sorts before 0003, which sorts before 4.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct VersionNumber {
+    value: u32,
+    width: usize,
+}
+
+impl VersionNumber {
+    /// constructor function. The number is displayed without padding
+    pub fn new(value: u32) -> Self {
+        Self { value, width: 0 }
+    }
+
+    /// Construct a number which is displayed zero padded to at least width digits
+    pub fn with_width(value: u32, width: usize) -> Self {
+        let digits = value.to_string().len();
+        Self {
+            value,
+            width: if width > digits { width } else { 0 },
+        }
+    }
+
+    /// Retrieve the numeric value
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Retrieve the minimum number of digits the number is displayed with.
+    /// This is 0 if the number is not zero padded
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Format the number according to the supplied convention
+    pub fn format_with(&self, format: &VersionFormat) -> String {
+        format.format(self.value)
+    }
+}
+
+impl From<u32> for VersionNumber {
+    fn from(value: u32) -> Self {
+        Self::new(value)
+    }
+}
+
+impl FromStr for VersionNumber {
+    type Err = AmuriError;
+
+    /// Parse a string of digits, remembering its width (eg 0003)
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let err = || AmuriError::StrToIntError {
+            target: input.into(),
+        };
+        if input.is_empty() || !input.chars().all(|c| c.is_ascii_digit()) {
+            return Err(err());
+        }
+        let value = input.parse().map_err(|_| err())?;
+        Ok(Self::with_width(value, input.len()))
+    }
+}
+
+impl fmt::Display for VersionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:0width$}", self.value, width = self.width)
+    }
+}
+
+/// A convention for formatting version numbers, such as the one used to
+/// name version directories on disk (eg v003)
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct VersionFormat {
+    prefix: String,
+    width: usize,
+}
+
+impl VersionFormat {
+    /// constructor function. Numbers are zero padded to at least width digits
+    pub fn new(width: usize) -> Self {
+        Self {
+            prefix: String::new(),
+            width,
+        }
+    }
+
+    /// Set a prefix which precedes the number (eg v)
+    pub fn with_prefix<I: Into<String>>(mut self, prefix: I) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Format the version number according to the convention
+    pub fn format(&self, value: u32) -> String {
+        format!("{}{:0width$}", self.prefix, value, width = self.width)
+    }
+}
+
+/// The version a relative selector offsets from
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Anchor {
+    Current,
+    Latest,
+}
+
+impl fmt::Display for Anchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Current => write!(f, "current"),
+            Self::Latest => write!(f, "latest"),
+        }
+    }
 }
 
 impl Version {
-    /// Generate Version from str
-    pub fn from_str(input: &str) -> Result<Self, AmuriError> {
+    /// Construct a numbered version
+    pub fn number(value: u32) -> Self {
+        Self::Number(VersionNumber::new(value))
+    }
+
+    /// Construct a tagged version. Returns an error if the name is not a
+    /// valid tag, or would be read back as another kind of version (eg
+    /// latest, previous or a number)
+    pub fn tag<I: Into<String>>(name: I) -> Result<Self, AmuriError> {
+        let name = name.into();
+        match Self::from_str(&name) {
+            Ok(tag @ Self::Tag(_)) => Ok(tag),
+            _ => Err(AmuriError::InvalidVersionTag(name)),
+        }
+    }
+
+    /// The version before the current version. This is the relative selector
+    /// current-1, and so is written as current-1 rather than previous
+    pub fn previous() -> Self {
+        Self::Relative {
+            anchor: Anchor::Current,
+            offset: -1,
+        }
+    }
+
+    /// Resolve the version to a concrete version number, given the version
+    /// numbers which are available and the version, if any, which the current
+    /// pointer references.
+    ///
+    /// - `Number` resolves to itself, provided that it is available
+    /// - `Latest` resolves to the highest available version
+    /// - `Current` resolves to the current pointer, provided that it is set and
+    ///   the version it references is available
+    /// - `Req` resolves to the highest available version satisfying the constraint
+    /// - `Relative` resolves the anchor as above, then steps through the
+    ///   available versions by the offset (eg latest-1 is the second highest
+    ///   available version)
+    ///
+    /// `Tag` cannot be resolved without tag bindings; see `resolve_with_tags`.
+    pub fn resolve(
+        &self,
+        available: &[u32],
+        current_pointer: Option<u32>,
+    ) -> Result<u32, AmuriError> {
+        self.resolve_with_tags(available, current_pointer, &BTreeMap::new())
+    }
+
+    /// Resolve the version as `resolve` does, additionally resolving a `Tag`
+    /// to the version number it is bound to in tags, provided that version is
+    /// available.
+    pub fn resolve_with_tags(
+        &self,
+        available: &[u32],
+        current_pointer: Option<u32>,
+        tags: &dyn TagResolver,
+    ) -> Result<u32, AmuriError> {
+        let not_found = || AmuriError::VersionNotFound {
+            version: self.clone(),
+        };
+        match self {
+            Self::Latest => available.iter().max().copied().ok_or_else(not_found),
+            Self::Current => {
+                let current = current_pointer.ok_or(AmuriError::CurrentVersionUnset)?;
+                if available.contains(&current) {
+                    Ok(current)
+                } else {
+                    Err(AmuriError::VersionNotFound {
+                        version: Self::number(current),
+                    })
+                }
+            }
+            Self::Number(num) => {
+                if available.contains(&num.value()) {
+                    Ok(num.value())
+                } else {
+                    Err(not_found())
+                }
+            }
+            Self::Req(req) => req.highest_match(available).ok_or_else(not_found),
+            Self::Relative { anchor, offset } => {
+                let base = match anchor {
+                    Anchor::Current => Self::Current,
+                    Anchor::Latest => Self::Latest,
+                }
+                .resolve_with_tags(available, current_pointer, tags)?;
+                let mut sorted = available.to_vec();
+                sorted.sort_unstable();
+                sorted.dedup();
+                // base has been resolved against available, so will be found
+                let index = sorted.binary_search(&base).unwrap_or_default() as i64;
+                let target = index + i64::from(*offset);
+                if target < 0 {
+                    return Err(not_found());
+                }
+                sorted.get(target as usize).copied().ok_or_else(not_found)
+            }
+            Self::Tag(tag) => {
+                let num = tags
+                    .resolve_tag(tag)
+                    .ok_or_else(|| AmuriError::TagUnbound(tag.clone()))?;
+                if available.contains(&num) {
+                    Ok(num)
+                } else {
+                    Err(AmuriError::VersionNotFound {
+                        version: Self::number(num),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Resolve the version as it would have resolved at as_of, using the
+    /// versions available, the current pointer and the tag bindings recorded
+    /// in the publish history at that time
+    pub fn resolve_as_of(
+        &self,
+        history: &dyn PublishHistory,
+        as_of: Timestamp,
+    ) -> Result<u32, AmuriError> {
+        self.resolve_with_tags(
+            &history.available_at(as_of),
+            history.current_at(as_of),
+            &TagsAsOf::new(history, as_of),
+        )
+    }
+}
+
+impl FromStr for Version {
+    type Err = AmuriError;
+
+    /// Generate Version from str. This may be current, latest, a number,
+    /// a constraint (eg >=3,<7 or 3..7 or ^12), a relative selector (eg
+    /// latest-1 or previous) or a tag (eg approved). Numbers remember their
+    /// zero padding (eg 0003). Previous is shorthand for current-1, and
+    /// displays as such
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
         match input {
             "current" => Ok(Self::Current),
             "latest" => Ok(Self::Latest),
-            _ => {
-                let num: u16 = input.parse().map_err(|_x| AmuriError::StrToIntError {
-                    target: input.into(),
-                })?;
-                Ok(Self::Number(num))
+            _ if input.starts_with(|c| "<>=^".contains(c)) || input.contains("..") => {
+                Ok(Self::Req(VersionReq::from_str(input)?))
             }
+            _ if input.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                if let Ok((_, version)) = all_consuming(parse_relative_version)(input) {
+                    return Ok(version);
+                }
+                all_consuming(parse_resource)(input)
+                    .map(|_| Self::Tag(input.into()))
+                    .map_err(|_| AmuriError::InvalidVersionTag(input.into()))
+            }
+            _ => Ok(Self::Number(VersionNumber::from_str(input)?)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Version {
+    type Error = AmuriError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::from_str(input)
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = AmuriError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::from_str(&input)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Current => write!(f, "current"),
+            Self::Latest => write!(f, "latest"),
+            Self::Number(num) => write!(f, "{}", num),
+            Self::Req(req) => write!(f, "{}", req),
+            Self::Relative { anchor, offset } => write!(f, "{}{:+}", anchor, offset),
+            Self::Tag(tag) => write!(f, "{}", tag),
         }
     }
 }
@@ -25,6 +359,200 @@ impl Version {
 #[cfg(test)]
 mod tests {
     use super::*;
+    mod version_number {
+        use super::*;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash(num: &VersionNumber) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            num.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        #[test]
+        fn padding_distinguishes_numbers() {
+            let padded = VersionNumber::from_str("0003").unwrap();
+            let plain = VersionNumber::new(3);
+            assert_ne!(padded, plain);
+            assert_ne!(hash(&padded), hash(&plain));
+            assert!(plain < padded);
+            assert_eq!(padded.width(), 4);
+            assert!(VersionNumber::from_str("0009").unwrap() < VersionNumber::new(10));
+            let unpadded = VersionNumber::from_str("3").unwrap();
+            assert_eq!(unpadded, plain);
+            assert_eq!(hash(&unpadded), hash(&plain));
+            assert_eq!(VersionNumber::with_width(12, 2), VersionNumber::new(12));
+        }
+        #[test]
+        fn can_display_with_padding() {
+            assert_eq!(VersionNumber::with_width(3, 4).to_string(), "0003");
+            assert_eq!(VersionNumber::with_width(12345, 4).to_string(), "12345");
+            assert_eq!(VersionNumber::new(3).to_string(), "3");
+        }
+        #[test]
+        fn can_format_with_convention() {
+            let format = VersionFormat::new(3).with_prefix("v");
+            assert_eq!(VersionNumber::new(3).format_with(&format), "v003");
+            assert_eq!(VersionNumber::with_width(3, 4).format_with(&format), "v003");
+            assert_eq!(VersionFormat::new(4).format(3), "0003");
+        }
+    }
+    mod resolve {
+        use super::*;
+        const AVAILABLE: [u32; 4] = [1, 2, 4, 3];
+
+        #[test]
+        fn number_resolves_to_itself() {
+            assert_eq!(Version::number(2).resolve(&AVAILABLE, None), Ok(2));
+        }
+        #[test]
+        fn missing_number_is_an_error() {
+            assert_eq!(
+                Version::number(7).resolve(&AVAILABLE, None),
+                Err(AmuriError::VersionNotFound {
+                    version: Version::number(7)
+                })
+            );
+        }
+        #[test]
+        fn latest_resolves_to_highest_available() {
+            assert_eq!(Version::Latest.resolve(&AVAILABLE, Some(2)), Ok(4));
+        }
+        #[test]
+        fn latest_with_nothing_available_is_an_error() {
+            assert_eq!(
+                Version::Latest.resolve(&[], None),
+                Err(AmuriError::VersionNotFound {
+                    version: Version::Latest
+                })
+            );
+        }
+        #[test]
+        fn current_resolves_to_current_pointer() {
+            assert_eq!(Version::Current.resolve(&AVAILABLE, Some(3)), Ok(3));
+        }
+        #[test]
+        fn current_requires_pointer() {
+            assert_eq!(
+                Version::Current.resolve(&AVAILABLE, None),
+                Err(AmuriError::CurrentVersionUnset)
+            );
+        }
+        #[test]
+        fn req_resolves_to_highest_match() {
+            let version = Version::from_str("<4").unwrap();
+            assert_eq!(version.resolve(&AVAILABLE, None), Ok(3));
+            let version = Version::from_str(">4").unwrap();
+            assert_eq!(
+                version.resolve(&AVAILABLE, None),
+                Err(AmuriError::VersionNotFound { version })
+            );
+        }
+        #[test]
+        fn relative_steps_through_available_versions() {
+            let version = Version::from_str("latest-1").unwrap();
+            assert_eq!(version.resolve(&AVAILABLE, None), Ok(3));
+            let version = Version::from_str("current+1").unwrap();
+            assert_eq!(version.resolve(&AVAILABLE, Some(2)), Ok(3));
+            let version = Version::from_str("current-1").unwrap();
+            assert_eq!(version.resolve(&[1, 2, 5, 9], Some(5)), Ok(2));
+            assert_eq!(Version::previous().resolve(&AVAILABLE, Some(4)), Ok(3));
+        }
+        #[test]
+        fn relative_beyond_available_versions_is_an_error() {
+            let version = Version::from_str("latest-4").unwrap();
+            assert_eq!(
+                version.resolve(&AVAILABLE, None),
+                Err(AmuriError::VersionNotFound {
+                    version: version.clone()
+                })
+            );
+            let version = Version::from_str("current+1").unwrap();
+            assert!(version.resolve(&AVAILABLE, Some(4)).is_err());
+            assert_eq!(
+                Version::previous().resolve(&AVAILABLE, None),
+                Err(AmuriError::CurrentVersionUnset)
+            );
+        }
+        fn tags() -> HashMap<String, u32> {
+            let mut tags = HashMap::new();
+            tags.insert("approved".to_string(), 2);
+            tags.insert("techcheck".to_string(), 9);
+            tags
+        }
+        #[test]
+        fn tag_resolves_to_bound_version() {
+            let version = Version::tag("approved").unwrap();
+            assert_eq!(version.resolve_with_tags(&AVAILABLE, None, &tags()), Ok(2));
+        }
+        #[test]
+        fn unbound_tag_is_an_error() {
+            assert_eq!(
+                Version::tag("client")
+                    .unwrap()
+                    .resolve_with_tags(&AVAILABLE, None, &tags()),
+                Err(AmuriError::TagUnbound("client".into()))
+            );
+            assert_eq!(
+                Version::tag("approved").unwrap().resolve(&AVAILABLE, None),
+                Err(AmuriError::TagUnbound("approved".into()))
+            );
+        }
+        #[test]
+        fn tag_bound_to_missing_version_is_an_error() {
+            assert_eq!(
+                Version::tag("techcheck")
+                    .unwrap()
+                    .resolve_with_tags(&AVAILABLE, None, &tags()),
+                Err(AmuriError::VersionNotFound {
+                    version: Version::number(9)
+                })
+            );
+        }
+        #[test]
+        fn resolves_as_of_a_point_in_time() {
+            use crate::history::PublishLog;
+            let at = Timestamp::from_secs;
+            let mut log = PublishLog::new();
+            log.publish(1, at(10))
+                .set_current(1, at(10))
+                .publish(2, at(20))
+                .set_tag("approved", 2, at(20))
+                .publish(3, at(30))
+                .set_current(3, at(30))
+                .set_tag("approved", 3, at(30));
+            assert_eq!(Version::Latest.resolve_as_of(&log, at(25)), Ok(2));
+            assert_eq!(Version::Current.resolve_as_of(&log, at(25)), Ok(1));
+            assert_eq!(
+                Version::tag("approved")
+                    .unwrap()
+                    .resolve_as_of(&log, at(25)),
+                Ok(2)
+            );
+            assert_eq!(
+                Version::tag("approved")
+                    .unwrap()
+                    .resolve_as_of(&log, at(30)),
+                Ok(3)
+            );
+            assert_eq!(
+                Version::number(3).resolve_as_of(&log, at(25)),
+                Err(AmuriError::VersionNotFound {
+                    version: Version::number(3)
+                })
+            );
+        }
+        #[test]
+        fn current_pointer_must_be_available() {
+            assert_eq!(
+                Version::Current.resolve(&AVAILABLE, Some(9)),
+                Err(AmuriError::VersionNotFound {
+                    version: Version::number(9)
+                })
+            );
+        }
+    }
     mod version {
         use super::*;
         #[test]
@@ -39,16 +567,148 @@ mod tests {
 
         #[test]
         fn can_generate_version_from_str_number() {
-            assert_eq!(Version::from_str("0001"), Ok(Version::Number(1)));
+            assert_eq!(
+                Version::from_str("0001"),
+                Ok(Version::Number(VersionNumber::with_width(1, 4)))
+            );
+        }
+        #[test]
+        fn can_generate_version_wider_than_16_bits() {
+            assert_eq!(Version::from_str("123456"), Ok(Version::number(123456)));
+        }
+        #[test]
+        fn version_number_remembers_padding() {
+            let version = Version::from_str("0003").unwrap();
+            assert_eq!(version.to_string(), "0003");
+            assert_eq!(Version::from_str("3").unwrap().to_string(), "3");
+        }
+        #[test]
+        fn version_number_rejects_sign() {
+            assert_eq!(
+                Version::from_str("+3"),
+                Err(AmuriError::StrToIntError {
+                    target: "+3".into()
+                })
+            );
+        }
+        #[test]
+        fn can_generate_version_from_str_req() {
+            assert_eq!(
+                Version::from_str("3..7"),
+                Ok(Version::Req(VersionReq::range(3, 7, false)))
+            );
+            assert_eq!(
+                Version::from_str("^12"),
+                Ok(Version::Req(VersionReq::at_least(12)))
+            );
+            assert_eq!(
+                Version::from_str(">=3,"),
+                Err(AmuriError::VersionReqParsingFailure(">=3,".into()))
+            );
+        }
+        #[test]
+        fn can_generate_version_from_str_relative() {
+            assert_eq!(
+                Version::from_str("latest-2"),
+                Ok(Version::Relative {
+                    anchor: Anchor::Latest,
+                    offset: -2
+                })
+            );
+            assert_eq!(Version::from_str("previous"), Ok(Version::previous()));
+        }
+        #[test]
+        fn can_generate_version_from_str_tag() {
+            assert_eq!(
+                Version::from_str("approved"),
+                Ok(Version::tag("approved").unwrap())
+            );
+            assert_eq!(
+                Version::from_str("client_review"),
+                Ok(Version::tag("client_review").unwrap())
+            );
+            assert_eq!(
+                Version::from_str("latest_approved"),
+                Ok(Version::tag("latest_approved").unwrap())
+            );
+        }
+        #[test]
+        fn tag_rejects_names_read_back_as_other_versions() {
+            for name in &[
+                "latest", "current", "previous", "latest-1", "0003", "a b", "",
+            ] {
+                assert_eq!(
+                    Version::tag(*name),
+                    Err(AmuriError::InvalidVersionTag(name.to_string()))
+                );
+            }
+        }
+        #[test]
+        fn will_return_error_if_given_invalid_tag() {
+            assert_eq!(
+                Version::from_str("approved_"),
+                Err(AmuriError::InvalidVersionTag("approved_".into()))
+            );
         }
         #[test]
         fn will_return_error_if_given_non_numeric_str() {
             assert_eq!(
-                Version::from_str("fred"),
+                Version::from_str("1fred"),
                 Err(AmuriError::StrToIntError {
-                    target: "fred".into()
+                    target: "1fred".into()
                 })
             );
         }
+        #[test]
+        fn can_generate_version_from_string() {
+            assert_eq!(Version::try_from("latest"), Ok(Version::Latest));
+            assert_eq!(
+                Version::try_from(String::from("12")),
+                Ok(Version::number(12))
+            );
+            assert_eq!("current".parse::<Version>(), Ok(Version::Current));
+        }
+        #[test]
+        fn versions_are_ordered() {
+            let mut versions = vec![
+                Version::number(10),
+                Version::Latest,
+                Version::number(2),
+                Version::Current,
+            ];
+            versions.sort();
+            assert_eq!(
+                versions,
+                vec![
+                    Version::Current,
+                    Version::Latest,
+                    Version::number(2),
+                    Version::number(10)
+                ]
+            );
+        }
+        #[test]
+        fn numbered_versions_are_ordered_numerically() {
+            assert!(Version::number(9) < Version::number(10));
+            assert_eq!(
+                Version::number(3).max(Version::number(12)),
+                Version::number(12)
+            );
+        }
+        #[test]
+        fn can_display_version() {
+            assert_eq!(Version::Current.to_string(), "current");
+            assert_eq!(Version::Latest.to_string(), "latest");
+            assert_eq!(Version::number(12).to_string(), "12");
+            assert_eq!(Version::previous().to_string(), "current-1");
+            assert_eq!(
+                Version::Relative {
+                    anchor: Anchor::Latest,
+                    offset: 2
+                }
+                .to_string(),
+                "latest+2"
+            );
+        }
     }
 }
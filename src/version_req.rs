@@ -0,0 +1,174 @@
+//! Version constraints
+//!
+//! A VersionReq is a set of comparators, all of which a version number
+//! must satisfy in order to match. eg `>=3,<7` matches 3, 4, 5 and 6
+use crate::errors::AmuriError;
+use crate::parse::version_req::parse_version_req;
+use nom::combinator::all_consuming;
+use std::fmt;
+use std::str::FromStr;
+
+/// Comparison operator
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Self::Exact => "=",
+            Self::Greater => ">",
+            Self::GreaterEq => ">=",
+            Self::Less => "<",
+            Self::LessEq => "<=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// An operator paired with the version number it compares against
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Comparator {
+    pub op: Op,
+    pub version: u32,
+}
+
+impl Comparator {
+    /// constructor function
+    pub fn new(op: Op, version: u32) -> Self {
+        Self { op, version }
+    }
+
+    /// Test whether the version number satisfies the comparator
+    pub fn matches(&self, version: u32) -> bool {
+        match self.op {
+            Op::Exact => version == self.version,
+            Op::Greater => version > self.version,
+            Op::GreaterEq => version >= self.version,
+            Op::Less => version < self.version,
+            Op::LessEq => version <= self.version,
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op, self.version)
+    }
+}
+
+/// A version constraint, consisting of one or more comparators
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    /// constructor function
+    pub fn new(comparators: Vec<Comparator>) -> Self {
+        Self { comparators }
+    }
+
+    /// Constraint matching versions from start up to end. The end is
+    /// included if inclusive is true
+    pub fn range(start: u32, end: u32, inclusive: bool) -> Self {
+        let end_op = if inclusive { Op::LessEq } else { Op::Less };
+        Self::new(vec![
+            Comparator::new(Op::GreaterEq, start),
+            Comparator::new(end_op, end),
+        ])
+    }
+
+    /// Constraint matching the version or anything newer
+    pub fn at_least(version: u32) -> Self {
+        Self::new(vec![Comparator::new(Op::GreaterEq, version)])
+    }
+
+    /// Retrieve the comparators
+    pub fn comparators(&self) -> &[Comparator] {
+        &self.comparators
+    }
+
+    /// Test whether the version number satisfies every comparator
+    pub fn matches(&self, version: u32) -> bool {
+        self.comparators.iter().all(|c| c.matches(version))
+    }
+
+    /// Retrieve the highest of the available versions which satisfies the
+    /// constraint
+    pub fn highest_match(&self, available: &[u32]) -> Option<u32> {
+        available.iter().copied().filter(|v| self.matches(*v)).max()
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = AmuriError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        all_consuming(parse_version_req)(input)
+            .map(|(_, req)| req)
+            .map_err(|_| AmuriError::VersionReqParsingFailure(input.into()))
+    }
+}
+
+impl fmt::Display for VersionReq {
+    /// Format the constraint as a comma separated list of comparators
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparators = self
+            .comparators
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", comparators.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_match_versions() {
+        let req = VersionReq::from_str(">=3,<7").unwrap();
+        assert!(!req.matches(2));
+        assert!(req.matches(3));
+        assert!(req.matches(6));
+        assert!(!req.matches(7));
+    }
+
+    #[test]
+    fn can_find_highest_match() {
+        let req = VersionReq::from_str("3..7").unwrap();
+        assert_eq!(req.highest_match(&[1, 4, 6, 7, 9]), Some(6));
+        assert_eq!(req.highest_match(&[1, 9]), None);
+    }
+
+    #[test]
+    fn caret_matches_version_or_newer() {
+        let req = VersionReq::from_str("^12").unwrap();
+        assert_eq!(req.highest_match(&[11, 12, 14]), Some(14));
+        assert_eq!(req.highest_match(&[11]), None);
+    }
+
+    #[test]
+    fn displays_as_comparators() {
+        assert_eq!(
+            VersionReq::from_str("3..=7").unwrap().to_string(),
+            ">=3,<=7"
+        );
+        assert_eq!(VersionReq::from_str("^12").unwrap().to_string(), ">=12");
+    }
+
+    #[test]
+    fn from_str_will_fail_with_invalid_input() {
+        assert_eq!(
+            VersionReq::from_str(">=3,"),
+            Err(AmuriError::VersionReqParsingFailure(">=3,".into()))
+        );
+    }
+}
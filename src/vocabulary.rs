@@ -0,0 +1,142 @@
+//! Known values for uri components, and "did you mean" suggestions
+//!
+//! A Vocabulary lists the departments, subcontexts and snapshot types that a
+//! caller considers valid. An empty list places no restriction on the
+//! component. When a component fails to parse, or is not in the vocabulary,
+//! the closest known values are offered as suggestions.
+use strsim::levenshtein;
+
+/// The maximum number of suggestions offered for a misspelled component
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Known values for the free-form components of a uri
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Vocabulary {
+    departments: Vec<String>,
+    subcontexts: Vec<String>,
+    snapshot_types: Vec<String>,
+}
+
+impl Vocabulary {
+    /// constructor function. The resulting Vocabulary places no restrictions
+    /// on any component
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the known departments
+    pub fn with_departments<I, S>(mut self, departments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.departments = departments.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the known subcontexts
+    pub fn with_subcontexts<I, S>(mut self, subcontexts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.subcontexts = subcontexts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the known snapshot types
+    pub fn with_snapshot_types<I, S>(mut self, snapshot_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.snapshot_types = snapshot_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Retrieve the known departments
+    pub fn departments(&self) -> &[String] {
+        &self.departments
+    }
+
+    /// Retrieve the known subcontexts
+    pub fn subcontexts(&self) -> &[String] {
+        &self.subcontexts
+    }
+
+    /// Retrieve the known snapshot types
+    pub fn snapshot_types(&self) -> &[String] {
+        &self.snapshot_types
+    }
+}
+
+/// Test whether the value is permitted by the list of known values. An empty
+/// list permits any value
+pub(crate) fn is_known(known: &[String], value: &str) -> bool {
+    known.is_empty() || known.iter().any(|k| k == value)
+}
+
+/// Return up to three candidates within a small edit distance of the input,
+/// closest first
+pub fn suggest<I, S>(input: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let threshold = (input.chars().count() / 3).max(2);
+    let mut scored = candidates
+        .into_iter()
+        .map(|c| (levenshtein(input, c.as_ref()), c.as_ref().to_string()))
+        .filter(|(distance, c)| *distance <= threshold && c != input)
+        .collect::<Vec<_>>();
+    scored.sort();
+    scored.dedup();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod suggest {
+        use super::*;
+
+        #[test]
+        fn suggests_closest_candidate_first() {
+            assert_eq!(
+                suggest("rendr", ["asset", "instance", "render", "plate"]),
+                vec!["render".to_string()]
+            );
+            assert_eq!(
+                suggest("modl", ["mode", "model", "anim"]),
+                vec!["mode".to_string(), "model".to_string()]
+            );
+        }
+
+        #[test]
+        fn ignores_distant_candidates() {
+            assert!(suggest("lighting", ["model", "anim"]).is_empty());
+        }
+    }
+
+    mod vocabulary {
+        use super::*;
+
+        #[test]
+        fn empty_vocabulary_permits_anything() {
+            let vocab = Vocabulary::new();
+            assert!(is_known(vocab.departments(), "anything"));
+        }
+
+        #[test]
+        fn vocabulary_restricts_values() {
+            let vocab = Vocabulary::new().with_departments(vec!["model", "anim"]);
+            assert!(is_known(vocab.departments(), "model"));
+            assert!(!is_known(vocab.departments(), "modl"));
+        }
+    }
+}